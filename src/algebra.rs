@@ -1,3 +1,5 @@
+use cargo_snippet::snippet;
+
 use crate::traits::Monoid;
 
 #[derive(Debug, Clone, Copy)]
@@ -11,3 +13,26 @@ impl Monoid for ZobHshP {
     }
     type S = u128;
 }
+
+/// 加法のモノイド
+#[snippet("Additive")]
+#[derive(Debug, Clone, Copy)]
+pub struct Additive<T>(std::marker::PhantomData<T>);
+#[snippet("Additive")]
+macro_rules! impl_additive {
+    ($($t:ty),*) => {
+        $(
+            impl Monoid for Additive<$t> {
+                type S = $t;
+                fn op(left: &Self::S, right: &Self::S) -> Self::S {
+                    left + right
+                }
+                fn e() -> Self::S {
+                    0
+                }
+            }
+        )*
+    };
+}
+#[snippet("Additive")]
+impl_additive!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
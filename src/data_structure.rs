@@ -1,6 +1,13 @@
 pub mod doubling;
+pub mod implicit_treap;
 pub mod lazy_segtree;
+pub mod link_cut_tree;
+pub mod merge_sort_tree;
 pub mod n_dim_acc;
 pub mod segtree;
+pub mod segtree_beats;
+pub mod sliding_window;
+pub mod sparse_table;
 pub mod swag;
 pub mod treap;
+pub mod wavelet_matrix;
@@ -1,122 +1,633 @@
 use crate::traits::{Doubling, Monoid};
 
+/// `table[k][i]`は`i`から`2^k`ターン先の行き先と、その間の重み
+type Table<S> = Vec<Vec<Option<(usize, S)>>>;
+
+/// `max_step`ターンまで表せる段数
+fn height(max_step: u64) -> usize {
+    (u64::BITS - max_step.leading_zeros()).max(1) as usize
+}
+
+/// `height`段の表で表せるターン数の上限
+fn max_step_of(height: usize) -> u64 {
+    if height >= 64 {
+        u64::MAX
+    } else {
+        (1 << height) - 1
+    }
+}
+
 pub struct WeightDoubling<T>
 where
     T: Monoid,
 {
-    table: Vec<Vec<Option<(usize, T::S)>>>,
+    table: Table<T::S>,
 }
-impl<T> Doubling for WeightDoubling<T>
+impl<T> WeightDoubling<T>
 where
     T: Monoid,
 {
-    type D = Option<(usize, T::S)>;
-    fn new(v: &[Self::D], k: usize) -> Self {
-        //! `ターンの最大値 <= 2^kとなるk`
-        let mut table = vec![vec![None; v.len()]; k];
-        for (i, val) in v.iter().enumerate() {
-            table[0][i] = val.clone();
-        }
-
-        for k in 0..k - 1 {
-            for i in 0..v.len() {
-                match &table[k][i] {
-                    Some((p, m)) => {
-                        let nval = table[k][*p].clone();
-                        match nval {
-                            Some((np, nm)) => table[k + 1][i] = Some((np, T::op(&m, &nm))),
-                            None => table[k + 1][i] = None,
-                        }
-                    }
-                    None => table[k + 1][i] = None,
+    fn build(&mut self) {
+        for k in 0..self.table.len() - 1 {
+            for i in 0..self.table[k].len() {
+                self.table[k + 1][i] = self.table[k][i].as_ref().and_then(|(p, m)| {
+                    let (np, nm) = self.table[k][*p].as_ref()?;
+                    Some((*np, T::op(m, nm)))
+                });
+            }
+        }
+    }
+    pub fn max_steps<F>(&self, idx: usize, pred: F) -> (u64, usize, T::S)
+    where
+        F: Fn(&T::S) -> bool,
+    {
+        //! `idx`から進んだときの重みの総積が`pred`を満たす、最大のターン数と、
+        //! そのときの行き先と総積を返す
+        //!
+        //! `pred(e) == true`で、`pred`はターン数について単調であること
+        //!
+        //! ターン数は`max_step`以下で、行き先がなくなる手前で止まる
+        //!
+        //! `O(log(max_step))`
+        let mut steps = 0;
+        let mut x = idx;
+        let mut m = T::e();
+        for (i, row) in self.table.iter().enumerate().rev() {
+            if let Some((nx, nm)) = &row[x] {
+                let nm = T::op(&m, nm);
+                if pred(&nm) {
+                    steps |= 1 << i;
+                    x = *nx;
+                    m = nm;
                 }
             }
         }
-        WeightDoubling { table }
+        (steps, x, m)
+    }
+}
+impl<T> Doubling for WeightDoubling<T>
+where
+    T: Monoid,
+{
+    type D = Option<(usize, T::S)>;
+    fn new(v: &[Self::D], max_step: u64) -> Self {
+        //! `max_step`ターンまで求められるように構築する
+        //!
+        //! `O(N log(max_step))`
+        let mut table = vec![vec![None; v.len()]; height(max_step)];
+        table[0] = v.to_vec();
+        let mut ret = WeightDoubling { table };
+        ret.build();
+        ret
+    }
+    fn max_step(&self) -> u64 {
+        max_step_of(self.table.len())
     }
-    fn query(&self, k: usize, idx: usize) -> Self::D {
+    fn query(&self, k: u64, idx: usize) -> Self::D {
+        //! `idx`から`k`ターン先の行き先と、その間の重みの総積
+        //!
+        //! 途中で行き先がなければ`None`
+        assert!(k <= self.max_step());
         let mut m = T::e();
         let mut x = idx;
-        for i in 0..=self.table.len() {
+        for (i, row) in self.table.iter().enumerate() {
             if k >> i & 1 == 1 {
-                // match &self.table[i][x] {
-                //     Some((nx, nm)) => {
-                //         x = nx.clone();
-                //         // m = m.op(nm);
-                //         m = T::op(&m, &nm);
-                //     }
-                //     None => return None,
-                // }
-                let (nx, nm) = self.table[i][x].clone()?;
-                x = nx;
-                m = T::op(&m, &nm);
+                let (nx, nm) = row[x].as_ref()?;
+                x = *nx;
+                m = T::op(&m, nm);
             }
         }
         Some((x, m))
     }
     fn set_next(&mut self, x: Self::D, idx: usize) {
+        //! 表を作り直すので`O(N log(max_step))`
         self.table[0][idx] = x;
+        self.build();
     }
 }
 
 pub struct UnWeightDoubling {
     table: Vec<Vec<Option<usize>>>,
 }
-impl Doubling for UnWeightDoubling {
-    type D = Option<usize>;
-
-    fn new(v: &[Self::D], k: usize) -> Self {
-        //! `ターンの最大値 <= 2^kとなるk`
-        let mut table = vec![vec![None; v.len()]; k];
-
-        for (i, val) in v.iter().enumerate() {
-            table[0][i] = val.clone();
+impl UnWeightDoubling {
+    fn build(&mut self) {
+        for k in 0..self.table.len() - 1 {
+            for i in 0..self.table[k].len() {
+                self.table[k + 1][i] = self.table[k][i].and_then(|p| self.table[k][p]);
+            }
         }
-
-        for k in 0..k - 1 {
-            for i in 0..v.len() {
-                table[k][i].map(|val| table[k + 1][i] = table[k][val]);
+    }
+    pub fn query_vec(&self, k: u64) -> Vec<Option<usize>> {
+        //! 全ての位置について、`k`ターン先の行き先
+        assert!(k <= self.max_step());
+        let mut ret = (0..self.table[0].len()).map(Some).collect::<Vec<_>>();
+        for (i, row) in self.table.iter().enumerate() {
+            if k >> i & 1 == 1 {
+                for x in ret.iter_mut() {
+                    *x = x.and_then(|j| row[j]);
+                }
             }
         }
+        ret
+    }
+}
+impl Doubling for UnWeightDoubling {
+    type D = Option<usize>;
 
-        UnWeightDoubling { table }
+    fn new(v: &[Self::D], max_step: u64) -> Self {
+        //! `max_step`ターンまで求められるように構築する
+        //!
+        //! `O(N log(max_step))`
+        let mut table = vec![vec![None; v.len()]; height(max_step)];
+        table[0] = v.to_vec();
+        let mut ret = UnWeightDoubling { table };
+        ret.build();
+        ret
+    }
+    fn max_step(&self) -> u64 {
+        max_step_of(self.table.len())
     }
-    fn query(&self, k: usize, idx: usize) -> Self::D {
+    fn query(&self, k: u64, idx: usize) -> Self::D {
+        assert!(k <= self.max_step());
         let mut x = idx;
-        for i in 0..=self.table.len() {
+        for (i, row) in self.table.iter().enumerate() {
             if k >> i & 1 == 1 {
-                // match self.table[i][x] {
-                //     Some(nx) => x = nx,
-                //     None => return None,
-                // }
-                x = self.table[i][x]?;
+                x = row[x]?;
             }
         }
         Some(x)
     }
     fn set_next(&mut self, x: Self::D, idx: usize) {
+        //! 表を作り直すので`O(N log(max_step))`
         self.table[0][idx] = x;
+        self.build();
     }
 }
-impl UnWeightDoubling {
-    pub fn query_vec(&self, k: usize) -> Vec<Option<usize>> {
-        let mut ret = (0..self.table[0].len())
-            .map(|i| Some(i))
-            .collect::<Vec<Option<usize>>>();
-        for i in 0..self.table.len() {
+
+/// 関数グラフ上の移動を、サイクル検出で求める
+///
+/// 各頂点について、サイクルに入るまでの長さ(`tail`)とサイクルの長さ(`period`)を持つ
+///
+/// サイクルに入る前の移動は、逆辺の森でのlevel ancestor(長い道の梯子と2冪の祖先)で求める
+///
+/// 構築`O(N log N)`、移動は`O(1)`
+pub struct FunctionalGraph {
+    tail: Vec<usize>,
+    /// `v`が最初に到達するサイクル上の頂点
+    root: Vec<usize>,
+    /// サイクル上の頂点について、サイクルの番号と、その中での位置
+    cycle_pos: Vec<(usize, usize)>,
+    cycles: Vec<Vec<usize>>,
+    /// `jump[j][v]`は`v`から`2^j`ターン先の頂点。サイクルに入ったら止まる
+    jump: Vec<Vec<usize>>,
+    /// 長い道の上に、同じ長さだけ祖先を足した列。サイクル側から順に並ぶ
+    ladders: Vec<Vec<usize>>,
+    /// `v`を含む長い道の梯子の番号と、その中での位置
+    ladder_pos: Vec<(usize, usize)>,
+}
+impl FunctionalGraph {
+    pub fn new(next: &[usize]) -> Self {
+        //! `next[v]`は`v`の1ターン先
+        let n = next.len();
+        // サイクルを列挙する
+        let mut state = vec![0u8; n];
+        let mut cycle_pos = vec![(usize::MAX, 0); n];
+        let mut cycles = vec![];
+        for s in 0..n {
+            let mut stack = vec![];
+            let mut v = s;
+            while state[v] == 0 {
+                state[v] = 1;
+                stack.push(v);
+                v = next[v];
+            }
+            if state[v] == 1 {
+                let start = stack.iter().position(|&x| x == v).unwrap();
+                let cycle = stack[start..].to_vec();
+                for (i, &x) in cycle.iter().enumerate() {
+                    cycle_pos[x] = (cycles.len(), i);
+                }
+                cycles.push(cycle);
+            }
+            for x in stack {
+                state[x] = 2;
+            }
+        }
+        // サイクル上の頂点から逆辺をたどる
+        let mut rev = vec![vec![]; n];
+        for v in 0..n {
+            if cycle_pos[v].0 == usize::MAX {
+                rev[next[v]].push(v);
+            }
+        }
+        let mut tail = vec![0usize; n];
+        let mut root = vec![0; n];
+        let mut order = Vec::with_capacity(n);
+        for r in cycles.iter().flatten().copied() {
+            root[r] = r;
+            let mut stack = vec![r];
+            while let Some(v) = stack.pop() {
+                order.push(v);
+                for &nv in &rev[v] {
+                    tail[nv] = tail[v] + 1;
+                    root[nv] = r;
+                    stack.push(nv);
+                }
+            }
+        }
+        // 葉までの最長の長さと、それを与える子
+        let mut height = vec![0; n];
+        let mut heavy = vec![usize::MAX; n];
+        for &v in order.iter().rev() {
+            for &nv in &rev[v] {
+                if heavy[v] == usize::MAX || height[v] < height[nv] + 1 {
+                    height[v] = height[nv] + 1;
+                    heavy[v] = nv;
+                }
+            }
+        }
+        let mut ladders = vec![];
+        let mut ladder_pos = vec![(0, 0); n];
+        for &top in &order {
+            if tail[top] != 0 && heavy[next[top]] == top {
+                continue;
+            }
+            // 道の長さだけ祖先を足す
+            let mut ladder = vec![];
+            let mut v = top;
+            for _ in 0..(height[top] + 1).min(tail[top]) {
+                v = next[v];
+                ladder.push(v);
+            }
+            ladder.reverse();
+            let mut v = top;
+            while v != usize::MAX {
+                ladder_pos[v] = (ladders.len(), ladder.len());
+                ladder.push(v);
+                v = heavy[v];
+            }
+            ladders.push(ladder);
+        }
+        let max_tail = tail.iter().copied().max().unwrap_or(0);
+        let log = (usize::BITS - max_tail.leading_zeros()) as usize;
+        let mut jump = vec![(0..n)
+            .map(|v| if tail[v] == 0 { v } else { next[v] })
+            .collect::<Vec<_>>()];
+        for j in 1..log {
+            let prev = &jump[j - 1];
+            let row = (0..n).map(|v| prev[prev[v]]).collect();
+            jump.push(row);
+        }
+        FunctionalGraph {
+            tail,
+            root,
+            cycle_pos,
+            cycles,
+            jump,
+            ladders,
+            ladder_pos,
+        }
+    }
+    pub fn tail(&self, v: usize) -> usize {
+        //! `v`からサイクルに入るまでのターン数
+        self.tail[v]
+    }
+    pub fn period(&self, v: usize) -> usize {
+        //! `v`が最終的に回るサイクルの長さ
+        self.cycles[self.cycle_pos[self.root[v]].0].len()
+    }
+    pub fn query(&self, k: u64, v: usize) -> usize {
+        //! `v`から`k`ターン先の頂点
+        let t = self.tail[v];
+        if k == 0 {
+            return v;
+        }
+        if k < t as u64 {
+            // 2冪だけ進むと、その先の長い道の梯子に残りが収まる
+            let k = k as usize;
+            let j = (usize::BITS - 1 - k.leading_zeros()) as usize;
+            let u = self.jump[j][v];
+            let (id, pos) = self.ladder_pos[u];
+            return self.ladders[id][pos - (k - (1 << j))];
+        }
+        let (id, pos) = self.cycle_pos[self.root[v]];
+        let cycle = &self.cycles[id];
+        let len = cycle.len() as u64;
+        cycle[((pos as u64 + (k - t as u64) % len) % len) as usize]
+    }
+}
+/// 木のダブリング
+///
+/// 頂点に`Monoid`の値を持ち、LCAとパス上の総積を`O(log N)`で求める
+pub struct TreeDoubling<T>
+where
+    T: Monoid,
+{
+    depth: Vec<usize>,
+    /// `table[k][v]`は`v`の`2^k`個上の祖先と、そこまでの(祖先を含まない)頂点の、上向きの総積
+    table: Table<T::S>,
+    /// `table`と同じ頂点の、下向きの総積
+    rev: Vec<Vec<T::S>>,
+    val: Vec<T::S>,
+}
+impl<T> TreeDoubling<T>
+where
+    T: Monoid,
+{
+    pub fn new(g: &[Vec<usize>], root: usize, val: &[T::S]) -> Self {
+        //! 隣接リスト`g`の、`root`を根とする木から構築する
+        //!
+        //! `root`と連結でない頂点は扱えない
+        //!
+        //! `O(N log N)`
+        let n = g.len();
+        assert_eq!(n, val.len());
+        let log = (usize::BITS - n.leading_zeros()).max(1) as usize;
+        let mut depth = vec![usize::MAX; n];
+        let mut table = vec![vec![None; n]; log];
+        let mut rev = vec![vec![T::e(); n]; log];
+        let mut q = std::collections::VecDeque::new();
+        depth[root] = 0;
+        q.push_back(root);
+        while let Some(v) = q.pop_front() {
+            for &nv in &g[v] {
+                if depth[nv] != usize::MAX {
+                    continue;
+                }
+                depth[nv] = depth[v] + 1;
+                table[0][nv] = Some((v, val[nv].clone()));
+                rev[0][nv] = val[nv].clone();
+                q.push_back(nv);
+            }
+        }
+        for k in 0..log - 1 {
+            for v in 0..n {
+                let Some((p, m)) = table[k][v].clone() else {
+                    continue;
+                };
+                if let Some((np, nm)) = table[k][p].clone() {
+                    table[k + 1][v] = Some((np, T::op(&m, &nm)));
+                    rev[k + 1][v] = T::op(&rev[k][p], &rev[k][v]);
+                }
+            }
+        }
+        TreeDoubling {
+            depth,
+            table,
+            rev,
+            val: val.to_vec(),
+        }
+    }
+    pub fn depth(&self, v: usize) -> usize {
+        self.depth[v]
+    }
+    pub fn kth_ancestor(&self, v: usize, k: usize) -> Option<usize> {
+        //! `v`の`k`個上の祖先
+        if k > self.depth[v] {
+            return None;
+        }
+        let mut v = v;
+        for (i, row) in self.table.iter().enumerate() {
             if k >> i & 1 == 1 {
-                ret = ret
-                    .iter()
-                    .map(|j| self.table[i][j.unwrap()])
-                    .collect::<Vec<Option<usize>>>();
+                v = row[v].as_ref()?.0;
             }
         }
-        ret
+        Some(v)
+    }
+    pub fn lca(&self, u: usize, v: usize) -> usize {
+        let (mut u, mut v) = if self.depth[u] < self.depth[v] {
+            (v, u)
+        } else {
+            (u, v)
+        };
+        u = self.kth_ancestor(u, self.depth[u] - self.depth[v]).unwrap();
+        if u == v {
+            return u;
+        }
+        for row in self.table.iter().rev() {
+            match (&row[u], &row[v]) {
+                (Some((nu, _)), Some((nv, _))) if nu != nv => {
+                    u = *nu;
+                    v = *nv;
+                }
+                _ => {}
+            }
+        }
+        self.table[0][u].as_ref().unwrap().0
+    }
+    pub fn dist(&self, u: usize, v: usize) -> usize {
+        //! `u`と`v`の間の辺の数
+        self.depth[u] + self.depth[v] - 2 * self.depth[self.lca(u, v)]
+    }
+    pub fn jump(&self, u: usize, v: usize, k: usize) -> Option<usize> {
+        //! `u`から`v`へのパスで、`k`番目 (`u`が0番目) の頂点
+        let l = self.lca(u, v);
+        let du = self.depth[u] - self.depth[l];
+        let dv = self.depth[v] - self.depth[l];
+        if k <= du {
+            self.kth_ancestor(u, k)
+        } else if k <= du + dv {
+            self.kth_ancestor(v, du + dv - k)
+        } else {
+            None
+        }
+    }
+    pub fn fold_path(&self, u: usize, v: usize) -> T::S {
+        //! `u`から`v`へのパス上の頂点の値の、`u`側からの総積
+        let l = self.lca(u, v);
+        let mut up = T::e();
+        let mut x = u;
+        let k = self.depth[u] - self.depth[l];
+        for (i, row) in self.table.iter().enumerate() {
+            if k >> i & 1 == 1 {
+                let (nx, m) = row[x].as_ref().unwrap();
+                up = T::op(&up, m);
+                x = *nx;
+            }
+        }
+        let mut down = T::e();
+        let mut x = v;
+        let k = self.depth[v] - self.depth[l];
+        for (i, row) in self.table.iter().enumerate() {
+            if k >> i & 1 == 1 {
+                down = T::op(&self.rev[i][x], &down);
+                x = row[x].as_ref().unwrap().0;
+            }
+        }
+        T::op(&T::op(&up, &self.val[l]), &down)
     }
 }
+
 #[cfg(test)]
 mod test {
+    use super::*;
+    use rand::Rng;
 
     #[test]
     fn it_work() {}
+
+    struct Concat;
+    impl Monoid for Concat {
+        type S = String;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            format!("{}{}", left, right)
+        }
+        fn e() -> Self::S {
+            String::new()
+        }
+    }
+
+    struct Add;
+    impl Monoid for Add {
+        type S = u64;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            left + right
+        }
+        fn e() -> Self::S {
+            0
+        }
+    }
+
+    #[test]
+    fn functional_graph() {
+        let n = 30;
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let next: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+        let fg = FunctionalGraph::new(&next);
+        let ud = UnWeightDoubling::new(
+            &next.iter().map(|&x| Some(x)).collect::<Vec<_>>(),
+            1e18 as u64,
+        );
+        let wd = WeightDoubling::<Add>::new(
+            &next
+                .iter()
+                .enumerate()
+                .map(|(i, &x)| Some((x, i as u64)))
+                .collect::<Vec<_>>(),
+            100,
+        );
+        assert_eq!(wd.max_step(), 127);
+        for v in 0..n {
+            let (mut x, mut w) = (v, 0);
+            for k in 0..=100 {
+                assert_eq!(fg.query(k, v), x);
+                assert_eq!(ud.query(k, v), Some(x));
+                assert_eq!(wd.query(k, v), Some((x, w)));
+                w += x as u64;
+                x = next[x];
+            }
+            assert_eq!(
+                fg.query(fg.tail(v) as u64 + fg.period(v) as u64, v),
+                fg.query(fg.tail(v) as u64, v)
+            );
+            for k in [1e18 as u64, 1e18 as u64 - 1, 999_999_999_989] {
+                assert_eq!(Some(fg.query(k, v)), ud.query(k, v));
+            }
+        }
+        assert_eq!(
+            ud.query_vec(1e18 as u64),
+            (0..n).map(|v| ud.query(1e18 as u64, v)).collect::<Vec<_>>()
+        );
+
+        // サイクルに入る前が長い
+        let n = 200;
+        let next: Vec<usize> = (0..n)
+            .map(|v| match v {
+                0 | 1 => 1 - v,
+                _ => v - 1 - rng.gen_range(0..2),
+            })
+            .collect();
+        let fg = FunctionalGraph::new(&next);
+        for v in 0..n {
+            let mut x = v;
+            for k in 0..=fg.tail(v) as u64 + 2 {
+                assert_eq!(fg.query(k, v), x);
+                x = next[x];
+            }
+        }
+        assert!((0..n).map(|v| fg.tail(v)).max().unwrap() > 100);
+
+        // 行き先のない位置を含む
+        let mut ud = UnWeightDoubling::new(&[Some(1), Some(2), None], 10);
+        assert_eq!(ud.query(1, 0), Some(1));
+        assert_eq!(ud.query(2, 0), Some(2));
+        assert_eq!(ud.query(3, 0), None);
+        assert_eq!(ud.query_vec(2), vec![Some(2), None, None]);
+        ud.set_next(Some(0), 2);
+        assert_eq!(ud.query(10, 0), Some(1));
+    }
+
+    #[test]
+    fn max_steps() {
+        // 0 -> 1 -> ... -> 9 -> 0 の重みつきサイクル
+        let n = 10;
+        let v: Vec<_> = (0..n).map(|i| Some(((i + 1) % n, i as u64 + 1))).collect();
+        let wd = WeightDoubling::<Add>::new(&v, 1 << 20);
+        for st in 0..n {
+            for lim in [0, 1, 5, 54, 55, 56, 1000, 123456] {
+                let (steps, x, m) = wd.max_steps(st, |&w| w <= lim);
+                let (mut ns, mut nx, mut nm) = (0, st, 0);
+                while nm + (nx as u64) < lim {
+                    nm += nx as u64 + 1;
+                    nx = (nx + 1) % n;
+                    ns += 1;
+                }
+                assert_eq!((steps, x, m), (ns, nx, nm));
+            }
+        }
+        // 行き先がなくなる手前で止まる
+        let wd = WeightDoubling::<Add>::new(&[Some((1, 1)), Some((2, 1)), None], 100);
+        assert_eq!(wd.max_steps(0, |_| true), (2, 2, 2));
+        // ターン数の上限で止まる
+        let wd = WeightDoubling::<Add>::new(&[Some((0, 1))], 100);
+        assert_eq!(wd.max_steps(0, |_| true), (127, 0, 127));
+    }
+
+    #[test]
+    fn tree_doubling() {
+        let n = 40;
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let mut par = vec![usize::MAX; n];
+        let mut g = vec![vec![]; n];
+        for v in 1..n {
+            par[v] = rng.gen_range(0..v);
+            g[v].push(par[v]);
+            g[par[v]].push(v);
+        }
+        let val: Vec<String> = (0..n).map(|v| format!("{},", v)).collect();
+        let td = TreeDoubling::<Concat>::new(&g, 0, &val);
+        let to_root = |mut v: usize| {
+            let mut path = vec![v];
+            while v != 0 {
+                v = par[v];
+                path.push(v);
+            }
+            path
+        };
+        for u in 0..n {
+            for v in 0..n {
+                let (pu, pv) = (to_root(u), to_root(v));
+                // 根側の共通部分を取り除いてパスを作る
+                let mut common = 0;
+                while common < pu.len().min(pv.len())
+                    && pu[pu.len() - 1 - common] == pv[pv.len() - 1 - common]
+                {
+                    common += 1;
+                }
+                let l = pu[pu.len() - common];
+                let mut path = pu[..pu.len() - common + 1].to_vec();
+                path.extend(pv[..pv.len() - common].iter().rev());
+                assert_eq!(td.lca(u, v), l);
+                assert_eq!(td.dist(u, v), path.len() - 1);
+                for k in 0..=path.len() {
+                    assert_eq!(td.jump(u, v, k), path.get(k).copied());
+                }
+                let s: String = path.iter().map(|&x| val[x].clone()).collect();
+                assert_eq!(td.fold_path(u, v), s);
+            }
+            for k in 0..n {
+                assert_eq!(td.kth_ancestor(u, k), to_root(u).get(k).copied());
+            }
+        }
+    }
 }
@@ -0,0 +1,535 @@
+use cargo_snippet::snippet;
+
+use crate::{data_structure::lazy_segtree::ForLazySegtree, traits::Monoid};
+
+#[snippet("ImplicitTreap")]
+const NIL: usize = usize::MAX;
+
+#[snippet("ImplicitTreap")]
+struct ImplicitTreapNode<T: ForLazySegtree> {
+    val: <T::M as Monoid>::S,
+    // 部分木の総積と、逆順の総積
+    sum: <T::M as Monoid>::S,
+    rev_sum: <T::M as Monoid>::S,
+    lazy: <T::L as Monoid>::S,
+    rev: bool,
+    priority: u64,
+    left: usize,
+    right: usize,
+    size: usize,
+}
+
+/// ノードの置き場。`split_off`で分けた`ImplicitTreap`どうしで共有する
+#[snippet("ImplicitTreap")]
+struct ImplicitTreapArena<T: ForLazySegtree> {
+    nodes: Vec<ImplicitTreapNode<T>>,
+    free: Vec<usize>,
+    seed: u64,
+}
+
+/// 位置をキーとする平衡二分木
+///
+/// 挿入、削除、分割、連結、区間反転、区間取得、区間作用が各 期待`O(log N)`
+///
+/// `split_off`で分けたものはノードの配列を共有する
+///
+/// 区間反転を使う場合、`mapping`は逆順の総積にも同様に作用できること
+///
+/// `try_mapping`が失敗する作用には対応しない
+#[snippet("ImplicitTreap")]
+pub struct ImplicitTreap<T: ForLazySegtree> {
+    arena: std::rc::Rc<std::cell::RefCell<ImplicitTreapArena<T>>>,
+    root: usize,
+}
+#[snippet("ImplicitTreap")]
+impl<T: ForLazySegtree> ImplicitTreap<T> {
+    pub fn new() -> Self {
+        ImplicitTreap {
+            arena: std::rc::Rc::new(std::cell::RefCell::new(ImplicitTreapArena {
+                nodes: vec![],
+                free: vec![],
+                seed: 88172645463325252,
+            })),
+            root: NIL,
+        }
+    }
+    pub fn build(v: &[<T::M as Monoid>::S]) -> Self {
+        //! `O(N)`
+        let mut treap = Self::new();
+        treap.root = treap.arena.borrow_mut().build_node(v);
+        treap
+    }
+    pub fn len(&self) -> usize {
+        self.arena.borrow().size_of(self.root)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.root == NIL
+    }
+    pub fn insert(&mut self, i: usize, x: <T::M as Monoid>::S) {
+        //! `i`番目に`x`を挿入する
+        assert!(i <= self.len());
+        let mut arena = self.arena.borrow_mut();
+        let (l, r) = arena.split_node(self.root, i);
+        let t = arena.new_node(x);
+        let l = arena.merge_node(l, t);
+        self.root = arena.merge_node(l, r);
+    }
+    pub fn push_back(&mut self, x: <T::M as Monoid>::S) {
+        let mut arena = self.arena.borrow_mut();
+        let t = arena.new_node(x);
+        self.root = arena.merge_node(self.root, t);
+    }
+    pub fn erase(&mut self, i: usize) -> <T::M as Monoid>::S {
+        //! `i`番目を削除して返す
+        assert!(i < self.len());
+        let mut arena = self.arena.borrow_mut();
+        let (l, r) = arena.split_node(self.root, i);
+        let (m, r) = arena.split_node(r, 1);
+        arena.free.push(m);
+        self.root = arena.merge_node(l, r);
+        arena.nodes[m].val.clone()
+    }
+    pub fn get(&mut self, i: usize) -> <T::M as Monoid>::S {
+        assert!(i < self.len());
+        self.fold(i..=i)
+    }
+    pub fn split_off(&mut self, i: usize) -> Self {
+        //! `i`番目以降を取り出して返す
+        //!
+        //! 返り値はノードの配列を共有する
+        assert!(i <= self.len());
+        let (l, r) = self.arena.borrow_mut().split_node(self.root, i);
+        self.root = l;
+        ImplicitTreap {
+            arena: self.arena.clone(),
+            root: r,
+        }
+    }
+    pub fn append(&mut self, other: &mut Self) {
+        //! `other`を後ろに連結し、`other`を空にする
+        //!
+        //! `other`が`split_off`で分けたもの(配列を共有する)なら期待`O(log N)`
+        //!
+        //! そうでなければ`other`の要素を移すので`O(log N + otherの要素数)`
+        let r = std::mem::replace(&mut other.root, NIL);
+        let r = if std::rc::Rc::ptr_eq(&self.arena, &other.arena) {
+            r
+        } else {
+            let mut src = other.arena.borrow_mut();
+            let mut v = Vec::with_capacity(src.size_of(r));
+            src.collect_node(r, &mut v);
+            src.free_subtree(r);
+            self.arena.borrow_mut().build_node(&v)
+        };
+        let mut arena = self.arena.borrow_mut();
+        self.root = arena.merge_node(self.root, r);
+    }
+    fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        use std::ops::Bound;
+        let l = match range.start_bound() {
+            Bound::Included(l) => *l,
+            Bound::Excluded(l) => l + 1,
+            Bound::Unbounded => 0,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(r) => r + 1,
+            Bound::Excluded(r) => *r,
+            Bound::Unbounded => r_max,
+        };
+        (l, r)
+    }
+    fn with_range<R, F, U>(&mut self, range: R, f: F) -> U
+    where
+        R: std::ops::RangeBounds<usize>,
+        F: FnOnce(&mut ImplicitTreapArena<T>, usize) -> U,
+    {
+        //! 区間を切り出した部分木に`f`を適用して戻す
+        let (l, r) = Self::range_to_tuple(range, self.len());
+        assert!(l <= r && r <= self.len());
+        let mut arena = self.arena.borrow_mut();
+        let (a, b) = arena.split_node(self.root, l);
+        let (b, c) = arena.split_node(b, r - l);
+        let ret = f(&mut arena, b);
+        let ab = arena.merge_node(a, b);
+        self.root = arena.merge_node(ab, c);
+        ret
+    }
+    pub fn fold<R>(&mut self, range: R) -> <T::M as Monoid>::S
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間取得
+        self.with_range(range, |arena, t| arena.sum_of(t))
+    }
+    pub fn apply_range<R>(&mut self, range: R, a: <T::L as Monoid>::S)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間作用
+        self.with_range(range, |arena, t| arena.apply_at(t, &a))
+    }
+    pub fn reverse<R>(&mut self, range: R)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間反転
+        self.with_range(range, |arena, t| arena.toggle_at(t))
+    }
+    pub fn to_vec(&mut self) -> Vec<<T::M as Monoid>::S> {
+        //! `O(N)`
+        let mut ret = Vec::with_capacity(self.len());
+        self.arena.borrow_mut().collect_node(self.root, &mut ret);
+        ret
+    }
+}
+
+#[snippet("ImplicitTreap")]
+impl<T: ForLazySegtree> ImplicitTreapArena<T> {
+    fn next_priority(&mut self) -> u64 {
+        //! xorshift
+        self.seed ^= self.seed << 7;
+        self.seed ^= self.seed >> 9;
+        self.seed
+    }
+    fn size_of(&self, t: usize) -> usize {
+        if t == NIL {
+            0
+        } else {
+            self.nodes[t].size
+        }
+    }
+    fn sum_of(&self, t: usize) -> <T::M as Monoid>::S {
+        if t == NIL {
+            T::M::e()
+        } else {
+            self.nodes[t].sum.clone()
+        }
+    }
+    fn rev_sum_of(&self, t: usize) -> <T::M as Monoid>::S {
+        if t == NIL {
+            T::M::e()
+        } else {
+            self.nodes[t].rev_sum.clone()
+        }
+    }
+    fn new_node(&mut self, x: <T::M as Monoid>::S) -> usize {
+        let node = ImplicitTreapNode {
+            val: x.clone(),
+            sum: x.clone(),
+            rev_sum: x,
+            lazy: T::L::e(),
+            rev: false,
+            priority: self.next_priority(),
+            left: NIL,
+            right: NIL,
+            size: 1,
+        };
+        if let Some(i) = self.free.pop() {
+            self.nodes[i] = node;
+            i
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+    fn build_node(&mut self, v: &[<T::M as Monoid>::S]) -> usize {
+        //! `v`を並べた部分木を作り、根を返す `O(N)`
+        //!
+        //! 優先度についてのCartesian treeを、右端の道をスタックに持って作る
+        let mut stack: Vec<usize> = vec![];
+        for x in v {
+            let t = self.new_node(x.clone());
+            let mut last = NIL;
+            while let Some(&top) = stack.last() {
+                if self.nodes[top].priority > self.nodes[t].priority {
+                    break;
+                }
+                stack.pop();
+                self.recalc(top);
+                last = top;
+            }
+            self.nodes[t].left = last;
+            if let Some(&top) = stack.last() {
+                self.nodes[top].right = t;
+            }
+            stack.push(t);
+        }
+        while let Some(t) = stack.pop() {
+            self.recalc(t);
+            if stack.is_empty() {
+                return t;
+            }
+        }
+        NIL
+    }
+    fn apply_at(&mut self, t: usize, a: &<T::L as Monoid>::S) {
+        //! 部分木全体に作用させ、子への作用を溜める
+        if t == NIL {
+            return;
+        }
+        let size = self.nodes[t].size;
+        let node = &mut self.nodes[t];
+        // 作用の失敗には対応しない
+        const MSG: &str = "try_mapping must not fail here";
+        node.val = T::try_mapping(&node.val, a, 1).expect(MSG);
+        node.sum = T::try_mapping(&node.sum, a, size).expect(MSG);
+        node.rev_sum = T::try_mapping(&node.rev_sum, a, size).expect(MSG);
+        node.lazy = T::L::op(&node.lazy, a);
+    }
+    fn toggle_at(&mut self, t: usize) {
+        //! 部分木全体を反転させ、子への反転を溜める
+        if t == NIL {
+            return;
+        }
+        let node = &mut self.nodes[t];
+        std::mem::swap(&mut node.left, &mut node.right);
+        std::mem::swap(&mut node.sum, &mut node.rev_sum);
+        node.rev ^= true;
+    }
+    fn propagate_at(&mut self, t: usize) {
+        let (left, right) = (self.nodes[t].left, self.nodes[t].right);
+        if self.nodes[t].rev {
+            self.toggle_at(left);
+            self.toggle_at(right);
+            self.nodes[t].rev = false;
+        }
+        let a = std::mem::replace(&mut self.nodes[t].lazy, T::L::e());
+        self.apply_at(left, &a);
+        self.apply_at(right, &a);
+    }
+    fn recalc(&mut self, t: usize) {
+        let (left, right) = (self.nodes[t].left, self.nodes[t].right);
+        let val = &self.nodes[t].val;
+        let sum = T::M::op(&T::M::op(&self.sum_of(left), val), &self.sum_of(right));
+        let rev_sum = T::M::op(
+            &T::M::op(&self.rev_sum_of(right), val),
+            &self.rev_sum_of(left),
+        );
+        let size = self.size_of(left) + 1 + self.size_of(right);
+        let node = &mut self.nodes[t];
+        node.sum = sum;
+        node.rev_sum = rev_sum;
+        node.size = size;
+    }
+    fn split_node(&mut self, t: usize, k: usize) -> (usize, usize) {
+        //! 先頭`k`個とそれ以外に分割する
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        self.propagate_at(t);
+        let left = self.nodes[t].left;
+        if k <= self.size_of(left) {
+            let (l, r) = self.split_node(left, k);
+            self.nodes[t].left = r;
+            self.recalc(t);
+            (l, t)
+        } else {
+            let k = k - self.size_of(left) - 1;
+            let (l, r) = self.split_node(self.nodes[t].right, k);
+            self.nodes[t].right = l;
+            self.recalc(t);
+            (t, r)
+        }
+    }
+    fn merge_node(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            self.propagate_at(a);
+            self.nodes[a].right = self.merge_node(self.nodes[a].right, b);
+            self.recalc(a);
+            a
+        } else {
+            self.propagate_at(b);
+            self.nodes[b].left = self.merge_node(a, self.nodes[b].left);
+            self.recalc(b);
+            b
+        }
+    }
+    fn collect_node(&mut self, t: usize, ret: &mut Vec<<T::M as Monoid>::S>) {
+        if t == NIL {
+            return;
+        }
+        self.propagate_at(t);
+        self.collect_node(self.nodes[t].left, ret);
+        ret.push(self.nodes[t].val.clone());
+        self.collect_node(self.nodes[t].right, ret);
+    }
+    fn free_subtree(&mut self, t: usize) {
+        //! `t`の部分木のノードを解放する
+        let mut stack = vec![t];
+        while let Some(t) = stack.pop() {
+            if t == NIL {
+                continue;
+            }
+            stack.push(self.nodes[t].left);
+            stack.push(self.nodes[t].right);
+            self.free.push(t);
+        }
+    }
+}
+
+#[snippet("ImplicitTreap")]
+impl<T: ForLazySegtree> Default for ImplicitTreap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[snippet("ImplicitTreap")]
+impl<T: ForLazySegtree> Drop for ImplicitTreap<T> {
+    fn drop(&mut self) {
+        // 配列を他と共有していれば、ノードを解放して再利用できるようにする
+        if std::rc::Rc::strong_count(&self.arena) > 1 {
+            self.arena.borrow_mut().free_subtree(self.root);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rand::Rng;
+
+    use super::ImplicitTreap;
+    use crate::{data_structure::lazy_segtree::ForLazySegtree, traits::Monoid};
+
+    // (総和, 位置で重み付けした和, 長さ) 非可換
+    // link_cut_treeのテストでも使う
+    pub(crate) struct WeightedSum;
+    impl Monoid for WeightedSum {
+        type S = (i64, i64, i64);
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            (
+                left.0 + right.0,
+                left.1 + right.1 + right.0 * left.2,
+                left.2 + right.2,
+            )
+        }
+        fn e() -> Self::S {
+            (0, 0, 0)
+        }
+    }
+    pub(crate) struct Add;
+    impl Monoid for Add {
+        type S = i64;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            left + right
+        }
+        fn e() -> Self::S {
+            0
+        }
+    }
+    pub(crate) struct WeightedSumAdd;
+    impl ForLazySegtree for WeightedSumAdd {
+        type M = WeightedSum;
+        type L = Add;
+        fn mapping(m: &(i64, i64, i64), l: &i64) -> (i64, i64, i64) {
+            (m.0 + l, m.1, m.2)
+        }
+        fn mapping_with_len(m: &(i64, i64, i64), l: &i64, len: usize) -> (i64, i64, i64) {
+            let len = len as i64;
+            (m.0 + l * len, m.1 + l * len * (len - 1) / 2, m.2)
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let mut treap = ImplicitTreap::<WeightedSumAdd>::new();
+        let mut v: Vec<(i64, i64, i64)> = vec![];
+        let fold = |v: &[(i64, i64, i64)]| {
+            v.iter()
+                .fold(WeightedSum::e(), |acc, x| WeightedSum::op(&acc, x))
+        };
+        for _ in 0..2000 {
+            let n = v.len();
+            match rng.gen_range(0..5) {
+                0 => {
+                    let i = rng.gen_range(0..=n);
+                    let a = rng.gen_range(0..100);
+                    let x = (a, 0, 1);
+                    treap.insert(i, x);
+                    v.insert(i, x);
+                }
+                1 if n > 0 => {
+                    let i = rng.gen_range(0..n);
+                    assert_eq!(treap.erase(i), v.remove(i));
+                }
+                2 => {
+                    let l = rng.gen_range(0..=n);
+                    let r = rng.gen_range(l..=n);
+                    treap.reverse(l..r);
+                    v[l..r].reverse();
+                }
+                3 => {
+                    let l = rng.gen_range(0..=n);
+                    let r = rng.gen_range(l..=n);
+                    let x = rng.gen_range(0..100);
+                    treap.apply_range(l..r, x);
+                    v[l..r].iter_mut().for_each(|a| a.0 += x);
+                }
+                _ => {
+                    let l = rng.gen_range(0..=n);
+                    let r = rng.gen_range(l..=n);
+                    assert_eq!(treap.fold(l..r), fold(&v[l..r]));
+                }
+            }
+            assert_eq!(treap.len(), v.len());
+        }
+        assert_eq!(treap.to_vec(), v);
+
+        let k = v.len() / 2;
+        let mut right = treap.split_off(k);
+        assert_eq!(treap.to_vec(), v[..k]);
+        assert_eq!(right.to_vec(), v[k..]);
+        treap.append(&mut right);
+        assert!(right.is_empty());
+        assert_eq!(treap.to_vec(), v);
+    }
+
+    #[test]
+    fn split_append() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(1);
+        let mut v: Vec<(i64, i64, i64)> = (0..500).map(|_| (rng.gen_range(0..100), 0, 1)).collect();
+        let mut treap = ImplicitTreap::<WeightedSumAdd>::build(&v);
+        assert_eq!(treap.to_vec(), v);
+        for _ in 0..200 {
+            let n = v.len();
+            let l = rng.gen_range(0..=n);
+            let r = rng.gen_range(l..=n);
+            // [l, r)を切り出して反転し、末尾に付け替える
+            let mut right = treap.split_off(l);
+            let mut rest = right.split_off(r - l);
+            right.reverse(..);
+            treap.append(&mut rest);
+            treap.append(&mut right);
+            assert!(rest.is_empty() && right.is_empty());
+            let mut mid = v.drain(l..r).rev().collect::<Vec<_>>();
+            v.append(&mut mid);
+            assert_eq!(treap.len(), v.len());
+        }
+        assert_eq!(treap.to_vec(), v);
+        // ノードを複製していない
+        assert_eq!(treap.arena.borrow().nodes.len(), v.len());
+
+        // 配列を共有しないもの同士の連結
+        let mut other = ImplicitTreap::<WeightedSumAdd>::build(&v[..10]);
+        other.apply_range(.., 1);
+        treap.append(&mut other);
+        assert!(other.is_empty());
+        v.extend(
+            v[..10]
+                .iter()
+                .map(|&(a, b, c)| (a + 1, b, c))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(treap.to_vec(), v);
+    }
+}
@@ -9,15 +9,48 @@ pub trait ForLazySegtree {
     /// Lazyに乗せるモノイド
     type L: Monoid;
     /// モノイドMに対して、Lをどう作用させるか
+    ///
+    /// `mapping_with_len`を実装する場合は、長さ1の区間への作用とする
     fn mapping(m: &<Self::M as Monoid>::S, l: &<Self::L as Monoid>::S) -> <Self::M as Monoid>::S;
+    /// ノードが表す区間の長さ`len`を使って作用させる
+    ///
+    /// デフォルトでは`mapping`を呼ぶ
+    fn mapping_with_len(
+        m: &<Self::M as Monoid>::S,
+        l: &<Self::L as Monoid>::S,
+        _len: usize,
+    ) -> <Self::M as Monoid>::S {
+        Self::mapping(m, l)
+    }
+    /// 作用に失敗する場合は`None`を返す。そのときは子に再帰して計算し直す
+    ///
+    /// 葉では必ず`Some`を返すこと。デフォルトでは`mapping_with_len`を呼ぶ
+    fn try_mapping(
+        m: &<Self::M as Monoid>::S,
+        l: &<Self::L as Monoid>::S,
+        len: usize,
+    ) -> Option<<Self::M as Monoid>::S> {
+        Some(Self::mapping_with_len(m, l, len))
+    }
 }
 
+/// `node[i]`は`lazy[i]`を作用済みの値を持ち、`lazy[i]`は子に未伝播の作用を持つ
 #[snippet("LazySegtree")]
 pub struct LazySegtree<T: ForLazySegtree> {
     original_size: usize,
     leaf_size: usize,
+    log: u32,
     node: Vec<<T::M as Monoid>::S>,
     lazy: Vec<<T::L as Monoid>::S>,
+    history: Option<Vec<History<T>>>,
+    /// `clear_history`で捨てた記録の数。チェックポイントは捨てた分だけずらす
+    history_base: usize,
+}
+/// ロールバック用に、書き換える前の値を記録する
+#[snippet("LazySegtree")]
+enum History<T: ForLazySegtree> {
+    Node(usize, <T::M as Monoid>::S),
+    Lazy(usize, <T::L as Monoid>::S),
 }
 #[snippet("LazySegtree")]
 impl<T: ForLazySegtree> LazySegtree<T> {
@@ -26,8 +59,11 @@ impl<T: ForLazySegtree> LazySegtree<T> {
         LazySegtree {
             original_size: n,
             leaf_size: size,
+            log: size.trailing_zeros(),
             node: vec![T::M::e(); 2 * size],
             lazy: vec![T::L::e(); 2 * size],
+            history: None,
+            history_base: 0,
         }
     }
     fn next_op(&self, i: usize) -> <T::M as Monoid>::S {
@@ -35,12 +71,11 @@ impl<T: ForLazySegtree> LazySegtree<T> {
     }
     pub fn build(&mut self, vec: &[<T::M as Monoid>::S]) {
         for (i, ele) in vec.iter().enumerate() {
-            let idx = i + self.leaf_size;
-            self.node[idx] = ele.clone();
+            self.set_node(i + self.leaf_size, ele.clone());
         }
         for i in (1..self.leaf_size).rev() {
             // self.node[i] = self.node[i << 1].op(&self.node[i << 1 | 1])
-            self.node[i] = self.next_op(i);
+            self.recalc_at(i);
         }
     }
     pub fn update(&mut self, i: usize, x: <T::M as Monoid>::S) {
@@ -48,10 +83,73 @@ impl<T: ForLazySegtree> LazySegtree<T> {
         assert!(i < self.original_size);
         let i = i + self.leaf_size;
         self.propagate_above(i);
-        self.node[i] = x;
-        self.lazy[i] = T::L::e();
+        self.set_node(i, x);
+        self.recalc_above(i);
+    }
+    pub fn update_with<F>(&mut self, i: usize, f: F)
+    where
+        F: FnOnce(&<T::M as Monoid>::S) -> <T::M as Monoid>::S,
+    {
+        //! `i`番目を`f(元の値)`に更新 O(log N)
+        assert!(i < self.original_size);
+        let i = i + self.leaf_size;
+        self.propagate_above(i);
+        self.set_node(i, f(&self.node[i]));
+        self.recalc_above(i);
+    }
+    pub fn get(&mut self, i: usize) -> <T::M as Monoid>::S {
+        //! 一点取得 O(log N)
+        assert!(i < self.original_size);
+        let i = i + self.leaf_size;
+        self.propagate_above(i);
+        self.node[i].clone()
+    }
+    pub fn apply_point(&mut self, i: usize, a: <T::L as Monoid>::S) {
+        //! 一点作用 O(log N)
+        assert!(i < self.original_size);
+        let i = i + self.leaf_size;
+        self.propagate_above(i);
+        self.apply_at(i, &a);
         self.recalc_above(i);
     }
+    pub fn to_vec(&mut self) -> Vec<<T::M as Monoid>::S> {
+        //! 全てのLazyを葉まで伝播し、元の配列を返す O(N)
+        for i in 1..self.leaf_size {
+            self.propagate_at(i);
+        }
+        self.node[self.leaf_size..self.leaf_size + self.original_size].to_vec()
+    }
+    pub fn snapshot(&mut self) -> usize {
+        //! 現在の状態をチェックポイントとして返す
+        //!
+        //! 以降、`node`と`lazy`への書き込みを記録する
+        //!
+        //! `fold`、`get`、`max_right`なども遅延の伝播で書き込むので、記録中は読むだけでも記録が増える
+        self.history_base + self.history.get_or_insert_with(Vec::new).len()
+    }
+    pub fn rollback(&mut self, checkpoint: usize) {
+        //! `checkpoint`を取った時点の状態に戻す
+        //!
+        //! `O(チェックポイント以降に書き換えた回数)`
+        let history = self.history.as_mut().expect("snapshot has not been taken");
+        assert!(checkpoint >= self.history_base, "checkpoint was cleared");
+        let checkpoint = checkpoint - self.history_base;
+        assert!(checkpoint <= history.len());
+        while history.len() > checkpoint {
+            match history.pop().unwrap() {
+                History::Node(i, x) => self.node[i] = x,
+                History::Lazy(i, a) => self.lazy[i] = a,
+            }
+        }
+    }
+    pub fn clear_history(&mut self) {
+        //! 記録を破棄し、以降は記録しない
+        //!
+        //! それまでのチェックポイントには戻せなくなる
+        if let Some(history) = self.history.take() {
+            self.history_base += history.len();
+        }
+    }
     fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
     where
         R: std::ops::RangeBounds<usize>,
@@ -76,28 +174,30 @@ impl<T: ForLazySegtree> LazySegtree<T> {
     {
         //! 区間取得 O(log N)
         let (l, r) = Self::range_to_tuple(range, self.original_size);
-        assert!(l < self.original_size);
+        assert!(l <= r);
         assert!(r <= self.original_size);
+        if l == r {
+            return T::M::e();
+        }
 
         let mut l = l + self.leaf_size;
         let mut r = r + self.leaf_size;
-
-        self.propagate_above(l / (l & (!l + 1)));
-        self.propagate_above((r / (r & (!r + 1))) - 1);
+        self.propagate_bounds(l, r);
         // self.propagate_above(l >> l.trailing_zeros());
         // self.propagate_above(r >> r.trailing_zeros()-1);
+
         let mut vl = T::M::e();
         let mut vr = T::M::e();
         while l < r {
             if l & 1 == 1 {
                 // vl = vl.op(&self.eval_at(l));
-                vl = T::M::op(&vl, &self.eval_at(l));
+                vl = T::M::op(&vl, &self.node[l]);
                 l += 1;
             }
             if r & 1 == 1 {
                 r -= 1;
                 // vr = self.eval_at(r).op(&vr);
-                vr = T::M::op(&self.eval_at(r), &vr)
+                vr = T::M::op(&self.node[r], &vr)
             }
             l >>= 1;
             r >>= 1;
@@ -110,29 +210,40 @@ impl<T: ForLazySegtree> LazySegtree<T> {
         R: std::ops::RangeBounds<usize>,
     {
         //! 区間作用 O(log N)
+        //!
+        //! `try_mapping`が失敗する場合は、失敗したノードの数だけ再帰する
         let (l, r) = Self::range_to_tuple(range, self.original_size);
-        let mut l = l + self.leaf_size;
-        let mut r = r + self.leaf_size;
-        let l0 = l / (l & (!l + 1));
-        let r0 = (r / (r & (!r + 1))) - 1;
-
-        self.propagate_above(l0);
-        self.propagate_above(r0);
+        assert!(l <= r);
+        assert!(r <= self.original_size);
+        if l == r {
+            return;
+        }
+        let l0 = l + self.leaf_size;
+        let r0 = r + self.leaf_size;
+        self.propagate_bounds(l0, r0);
 
+        let mut l = l0;
+        let mut r = r0;
         while l < r {
             if l & 1 == 1 {
-                self.lazy[l] = T::L::op(&self.lazy[l], &a);
+                self.apply_at(l, &a);
                 l += 1;
             }
             if r & 1 == 1 {
                 r -= 1;
-                self.lazy[r] = T::L::op(&self.lazy[r], &a);
+                self.apply_at(r, &a);
             }
             l >>= 1;
             r >>= 1;
         }
-        self.recalc_above(l0);
-        self.recalc_above(r0);
+        for k in 1..=self.log {
+            if (l0 >> k) << k != l0 {
+                self.recalc_at(l0 >> k);
+            }
+            if (r0 >> k) << k != r0 {
+                self.recalc_at((r0 - 1) >> k);
+            }
+        }
     }
     pub fn max_right<F>(&mut self, l: usize, f: F) -> usize
     where
@@ -141,10 +252,13 @@ impl<T: ForLazySegtree> LazySegtree<T> {
         //! `f(l..r) == true`となる最大のrを探索
         assert!(l <= self.original_size);
         assert!(f(&T::M::e()));
+        if l == self.original_size {
+            return self.original_size;
+        }
 
         let mut l = l + self.leaf_size;
         let mut v = T::M::e();
-        self.propagate_above(l / (l & (!l + 1)));
+        self.propagate_above(l);
 
         loop {
             // 右ノードになるまで親に移動
@@ -169,7 +283,9 @@ impl<T: ForLazySegtree> LazySegtree<T> {
             if !f(&T::M::op(&v, &self.node[l])) {
                 while l < self.leaf_size {
                     self.propagate_at(l);
+                    // 左ノードに移動
                     l <<= 1;
+                    // 左ノードがtrueなら右ノードがfalse
                     if f(&T::M::op(&v, &self.node[l])) {
                         v = T::M::op(&v, &self.node[l]);
                         l += 1;
@@ -181,11 +297,8 @@ impl<T: ForLazySegtree> LazySegtree<T> {
             // v = v.op(&self.node[l]);
             v = T::M::op(&v, &self.node[l]);
             l += 1; // 隣のノードに移動し、また区間を二倍にしつつ探索するloopに入る
-            {
-                let l = l as isize;
-                if l & -l == l {
-                    break;
-                }
+            if l.is_power_of_two() {
+                break;
             }
         }
         self.original_size
@@ -198,10 +311,13 @@ impl<T: ForLazySegtree> LazySegtree<T> {
         //! `f(l..r) == true`となる最小のlを探索
         assert!(r <= self.original_size);
         assert!(f(&T::M::e()));
+        if r == 0 {
+            return 0;
+        }
 
         let mut r = r + self.leaf_size;
         let mut v = T::M::e();
-        self.propagate_above((r / (r & (!r + 1))) - 1);
+        self.propagate_above(r - 1);
 
         loop {
             r -= 1;
@@ -235,11 +351,8 @@ impl<T: ForLazySegtree> LazySegtree<T> {
             }
             // v = self.node[r].op(&v);
             v = T::M::op(&self.node[r], &v);
-            {
-                let r = r as isize;
-                if r & -r == r {
-                    break;
-                }
+            if r.is_power_of_two() {
+                break;
             }
         }
         0
@@ -248,35 +361,268 @@ impl<T: ForLazySegtree> LazySegtree<T> {
 
 #[snippet("LazySegtree")]
 impl<T: ForLazySegtree> LazySegtree<T> {
-    fn eval_at(&mut self, i: usize) -> <T::M as Monoid>::S {
-        //! node[i]にlazy[i]を作用
-        T::mapping(&self.node[i], &self.lazy[i])
+    fn set_node(&mut self, i: usize, x: <T::M as Monoid>::S) {
+        let old = std::mem::replace(&mut self.node[i], x);
+        if let Some(history) = &mut self.history {
+            history.push(History::Node(i, old));
+        }
+    }
+    fn set_lazy(&mut self, i: usize, a: <T::L as Monoid>::S) -> <T::L as Monoid>::S {
+        //! 書き換える前の値を返す
+        let old = std::mem::replace(&mut self.lazy[i], a);
+        if let Some(history) = &mut self.history {
+            history.push(History::Lazy(i, old.clone()));
+        }
+        old
+    }
+    fn len_at(&self, i: usize) -> usize {
+        //! node[i]が表す区間のうち、元の配列に含まれる部分の長さ
+        let depth = usize::BITS - 1 - i.leading_zeros();
+        let width = self.leaf_size >> depth;
+        let st = (i - (1 << depth)) * width;
+        (st + width).min(self.original_size).saturating_sub(st)
+    }
+    fn recalc_at(&mut self, i: usize) {
+        self.set_node(i, self.next_op(i));
+    }
+    fn apply_at(&mut self, i: usize, a: &<T::L as Monoid>::S) {
+        //! node[i]にaを作用させ、lazy[i]に合成する
+        //!
+        //! 作用に失敗したら子に再帰する
+        match T::try_mapping(&self.node[i], a, self.len_at(i)) {
+            Some(x) => {
+                self.set_node(i, x);
+                if i < self.leaf_size {
+                    self.set_lazy(i, T::L::op(&self.lazy[i], a));
+                }
+            }
+            None => {
+                assert!(i < self.leaf_size, "try_mapping failed at a leaf");
+                self.set_lazy(i, T::L::op(&self.lazy[i], a));
+                self.propagate_at(i);
+                self.recalc_at(i);
+            }
+        }
     }
     fn propagate_at(&mut self, i: usize) {
-        //! lazyの合成
-        //! 非可換を想定しているので、lazy[i]にあるLをnode[i]に作用させる
-        self.node[i] = self.eval_at(i);
+        //! lazy[i]を子に伝播
+        //! 非可換を想定しているので、子のlazyの後ろに合成する
+        let a = self.set_lazy(i, T::L::e());
         // i<<1にiから合成
-        self.lazy[i << 1] = T::L::op(&self.lazy[i << 1], &self.lazy[i]);
+        self.apply_at(i << 1, &a);
         // i<<1|1にiから合成
-        self.lazy[i << 1 | 1] = T::L::op(&self.lazy[i << 1 | 1], &self.lazy[i]);
-        self.lazy[i] = T::L::e();
+        self.apply_at(i << 1 | 1, &a);
     }
     // iより上ののLazyを合成
     // i == 8のとき、1, 2, 4の順
     fn propagate_above(&mut self, i: usize) {
         // let h = self.exp;
-        let h = std::usize::MAX.count_ones() - i.leading_zeros();
-        for k in (1..h).rev() {
+        for k in (1..=self.log).rev() {
             self.propagate_at(i >> k);
         }
     }
+    // [l, r)の両端に関わるノードのLazyを上から伝播
+    fn propagate_bounds(&mut self, l: usize, r: usize) {
+        for k in (1..=self.log).rev() {
+            if (l >> k) << k != l {
+                self.propagate_at(l >> k);
+            }
+            if (r >> k) << k != r {
+                self.propagate_at((r - 1) >> k);
+            }
+        }
+    }
     // 上部のnodeを計算
     fn recalc_above(&mut self, i: usize) {
-        let mut i = i;
-        while i > 1 {
-            i >>= 1;
-            self.node[i] = T::M::op(&self.eval_at(i << 1), &self.eval_at(i << 1 | 1))
+        for k in 1..=self.log {
+            self.recalc_at(i >> k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::{ForLazySegtree, LazySegtree};
+    use crate::traits::Monoid;
+
+    struct Sum;
+    impl Monoid for Sum {
+        type S = i64;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            left + right
+        }
+        fn e() -> Self::S {
+            0
+        }
+    }
+
+    #[test]
+    fn add_sum_with_len() {
+        struct AddSum;
+        impl ForLazySegtree for AddSum {
+            type M = Sum;
+            type L = Sum;
+            fn mapping(m: &i64, l: &i64) -> i64 {
+                m + l
+            }
+            fn mapping_with_len(m: &i64, l: &i64, len: usize) -> i64 {
+                m + l * len as i64
+            }
+        }
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let n = 13;
+        let mut v = (0..n)
+            .map(|_| rng.gen_range(-10..=10))
+            .collect::<Vec<i64>>();
+        let mut seg = LazySegtree::<AddSum>::new(n);
+        seg.build(&v);
+        for _ in 0..500 {
+            let l = rng.gen_range(0..=n);
+            let r = rng.gen_range(l..=n);
+            if rng.gen_bool(0.5) {
+                let x = rng.gen_range(-10..=10);
+                seg.apply_range(l..r, x);
+                v[l..r].iter_mut().for_each(|a| *a += x);
+            } else {
+                assert_eq!(seg.fold(l..r), v[l..r].iter().sum::<i64>());
+            }
+        }
+        let l = rng.gen_range(0..=n);
+        let r = seg.max_right(l, |&s| s <= 20);
+        assert!(v[l..r].iter().sum::<i64>() <= 20);
+
+        seg.apply_point(3, 100);
+        v[3] += 100;
+        seg.update_with(5, |&x| x * 2);
+        v[5] *= 2;
+        assert_eq!(seg.get(3), v[3]);
+        assert_eq!(seg.get(5), v[5]);
+        assert_eq!(seg.fold(..), v.iter().sum::<i64>());
+        assert_eq!(seg.to_vec(), v);
+    }
+
+    #[test]
+    fn rollback() {
+        struct AddSum;
+        impl ForLazySegtree for AddSum {
+            type M = Sum;
+            type L = Sum;
+            fn mapping(m: &i64, l: &i64) -> i64 {
+                m + l
+            }
+            fn mapping_with_len(m: &i64, l: &i64, len: usize) -> i64 {
+                m + l * len as i64
+            }
+        }
+        let mut rng = rand_pcg::Pcg64Mcg::new(2);
+        let n = 10;
+        let v0 = (0..n)
+            .map(|_| rng.gen_range(-10..=10))
+            .collect::<Vec<i64>>();
+        let mut seg = LazySegtree::<AddSum>::new(n);
+        seg.build(&v0);
+
+        let mut v = v0.clone();
+        let mut checkpoints = vec![(seg.snapshot(), v.clone())];
+        for _ in 0..30 {
+            let l = rng.gen_range(0..=n);
+            let r = rng.gen_range(l..=n);
+            let x = rng.gen_range(-10..=10);
+            seg.apply_range(l..r, x);
+            v[l..r].iter_mut().for_each(|a| *a += x);
+            let i = rng.gen_range(0..n);
+            seg.update(i, x);
+            v[i] = x;
+            assert_eq!(seg.fold(l..r), v[l..r].iter().sum::<i64>());
+            checkpoints.push((seg.snapshot(), v.clone()));
+        }
+        while let Some((checkpoint, v)) = checkpoints.pop() {
+            seg.rollback(checkpoint);
+            for l in 0..n {
+                assert_eq!(seg.fold(l..), v[l..].iter().sum::<i64>());
+            }
+        }
+        assert_eq!(seg.to_vec(), v0);
+
+        // 破棄した後のチェックポイントは、破棄する前のものと被らない
+        seg.apply_range(.., 1);
+        let old = seg.snapshot();
+        seg.apply_range(.., 1);
+        seg.clear_history();
+        let new = seg.snapshot();
+        assert!(old < new);
+        seg.apply_range(.., 1);
+        seg.rollback(new);
+        assert_eq!(seg.to_vec(), v0.iter().map(|x| x + 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn chmin_sum_with_failure() {
+        // (sum, max, maxの個数, 2番目のmax)
+        struct M;
+        impl Monoid for M {
+            type S = (i64, i64, i64, i64);
+            fn op(left: &Self::S, right: &Self::S) -> Self::S {
+                let (max, cnt, max2) = match left.1.cmp(&right.1) {
+                    std::cmp::Ordering::Greater => (left.1, left.2, left.3.max(right.1)),
+                    std::cmp::Ordering::Less => (right.1, right.2, right.3.max(left.1)),
+                    std::cmp::Ordering::Equal => (left.1, left.2 + right.2, left.3.max(right.3)),
+                };
+                (left.0 + right.0, max, cnt, max2)
+            }
+            fn e() -> Self::S {
+                (0, i64::MIN, 0, i64::MIN)
+            }
+        }
+        struct Chmin;
+        impl Monoid for Chmin {
+            type S = i64;
+            fn op(left: &Self::S, right: &Self::S) -> Self::S {
+                *left.min(right)
+            }
+            fn e() -> Self::S {
+                i64::MAX
+            }
+        }
+        struct ChminSum;
+        impl ForLazySegtree for ChminSum {
+            type M = M;
+            type L = Chmin;
+            fn mapping(m: &(i64, i64, i64, i64), l: &i64) -> (i64, i64, i64, i64) {
+                // 葉
+                (m.0.min(*l), m.1.min(*l), m.2, m.3)
+            }
+            fn try_mapping(
+                m: &(i64, i64, i64, i64),
+                l: &i64,
+                _: usize,
+            ) -> Option<(i64, i64, i64, i64)> {
+                if m.1 <= *l {
+                    Some(*m)
+                } else if m.3 < *l {
+                    Some((m.0 - (m.1 - l) * m.2, *l, m.2, m.3))
+                } else {
+                    None
+                }
+            }
+        }
+        let mut rng = rand_pcg::Pcg64Mcg::new(1);
+        let n = 11;
+        let mut v = (0..n).map(|_| rng.gen_range(0..=100)).collect::<Vec<i64>>();
+        let mut seg = LazySegtree::<ChminSum>::new(n);
+        seg.build(&v.iter().map(|&x| (x, x, 1, i64::MIN)).collect::<Vec<_>>());
+        for _ in 0..500 {
+            let l = rng.gen_range(0..=n);
+            let r = rng.gen_range(l..=n);
+            if rng.gen_bool(0.5) {
+                let x = rng.gen_range(0..=100);
+                seg.apply_range(l..r, x);
+                v[l..r].iter_mut().for_each(|a| *a = (*a).min(x));
+            } else {
+                assert_eq!(seg.fold(l..r).0, v[l..r].iter().sum::<i64>());
+            }
         }
     }
 }
@@ -0,0 +1,373 @@
+use cargo_snippet::snippet;
+
+use crate::{data_structure::lazy_segtree::ForLazySegtree, traits::Monoid};
+
+#[snippet("LinkCutTree")]
+const NIL: usize = usize::MAX;
+
+#[snippet("LinkCutTree")]
+struct LinkCutNode<T: ForLazySegtree> {
+    val: <T::M as Monoid>::S,
+    // splay木の部分木の総積と、逆順の総積
+    sum: <T::M as Monoid>::S,
+    rev_sum: <T::M as Monoid>::S,
+    lazy: <T::L as Monoid>::S,
+    rev: bool,
+    left: usize,
+    right: usize,
+    parent: usize,
+    size: usize,
+}
+
+/// 森に対する辺の追加、削除とパスクエリ
+///
+/// 各操作 ならし`O(log N)`
+///
+/// パス作用を使う場合、`mapping`は逆順の総積にも同様に作用できること
+///
+/// `try_mapping`が失敗する作用には対応しない
+#[snippet("LinkCutTree")]
+pub struct LinkCutTree<T: ForLazySegtree> {
+    nodes: Vec<LinkCutNode<T>>,
+}
+#[snippet("LinkCutTree")]
+impl<T: ForLazySegtree> LinkCutTree<T> {
+    pub fn new(n: usize) -> Self {
+        Self::build(&vec![T::M::e(); n])
+    }
+    pub fn build(v: &[<T::M as Monoid>::S]) -> Self {
+        //! 頂点`i`の値が`v[i]`の、辺のない森
+        let nodes = v
+            .iter()
+            .map(|x| LinkCutNode {
+                val: x.clone(),
+                sum: x.clone(),
+                rev_sum: x.clone(),
+                lazy: T::L::e(),
+                rev: false,
+                left: NIL,
+                right: NIL,
+                parent: NIL,
+                size: 1,
+            })
+            .collect();
+        LinkCutTree { nodes }
+    }
+    pub fn link(&mut self, u: usize, v: usize) {
+        //! 辺`(u, v)`を追加する。`u`と`v`は別の木にあること
+        assert!(!self.is_connected(u, v), "u and v are already connected");
+        self.evert(u);
+        self.nodes[u].parent = v;
+    }
+    pub fn cut(&mut self, u: usize, v: usize) {
+        //! 辺`(u, v)`を削除する。辺`(u, v)`が存在すること
+        self.evert(u);
+        self.expose(v);
+        assert!(
+            self.nodes[v].left == u && self.nodes[u].left == NIL && self.nodes[u].right == NIL,
+            "edge (u, v) does not exist"
+        );
+        self.nodes[v].left = NIL;
+        self.nodes[u].parent = NIL;
+        self.recalc(v);
+    }
+    pub fn evert(&mut self, v: usize) {
+        //! `v`を根にする
+        self.expose(v);
+        self.toggle(v);
+        self.propagate_at(v);
+    }
+    pub fn root(&mut self, v: usize) -> usize {
+        //! `v`を含む木の根
+        self.expose(v);
+        let mut v = v;
+        loop {
+            self.propagate_at(v);
+            if self.nodes[v].left == NIL {
+                break;
+            }
+            v = self.nodes[v].left;
+        }
+        self.splay(v);
+        v
+    }
+    pub fn is_connected(&mut self, u: usize, v: usize) -> bool {
+        self.root(u) == self.root(v)
+    }
+    pub fn lca(&mut self, u: usize, v: usize) -> Option<usize> {
+        //! 現在の根に対する`u`と`v`のLCA。別の木にあれば`None`
+        if !self.is_connected(u, v) {
+            return None;
+        }
+        self.expose(u);
+        Some(self.expose(v))
+    }
+    pub fn get(&mut self, v: usize) -> <T::M as Monoid>::S {
+        self.expose(v);
+        self.nodes[v].val.clone()
+    }
+    pub fn set(&mut self, v: usize, x: <T::M as Monoid>::S) {
+        self.expose(v);
+        self.nodes[v].val = x;
+        self.recalc(v);
+    }
+    pub fn fold_path(&mut self, u: usize, v: usize) -> <T::M as Monoid>::S {
+        //! `u`から`v`へのパス上の頂点の総積。`u`と`v`は連結であること
+        //!
+        //! 呼び出し後は`u`が根になる
+        self.evert(u);
+        self.expose(v);
+        self.nodes[v].sum.clone()
+    }
+    pub fn apply_path(&mut self, u: usize, v: usize, a: <T::L as Monoid>::S) {
+        //! `u`から`v`へのパス上の頂点に作用させる。`u`と`v`は連結であること
+        //!
+        //! 呼び出し後は`u`が根になる
+        self.evert(u);
+        self.expose(v);
+        self.apply_at(v, &a);
+    }
+}
+
+#[snippet("LinkCutTree")]
+impl<T: ForLazySegtree> LinkCutTree<T> {
+    fn is_root(&self, v: usize) -> bool {
+        //! splay木の根か
+        let p = self.nodes[v].parent;
+        p == NIL || (self.nodes[p].left != v && self.nodes[p].right != v)
+    }
+    fn recalc(&mut self, v: usize) {
+        let (left, right) = (self.nodes[v].left, self.nodes[v].right);
+        let (mut sum, mut rev_sum, mut size) =
+            (self.nodes[v].val.clone(), self.nodes[v].val.clone(), 1);
+        if left != NIL {
+            sum = T::M::op(&self.nodes[left].sum, &sum);
+            rev_sum = T::M::op(&rev_sum, &self.nodes[left].rev_sum);
+            size += self.nodes[left].size;
+        }
+        if right != NIL {
+            sum = T::M::op(&sum, &self.nodes[right].sum);
+            rev_sum = T::M::op(&self.nodes[right].rev_sum, &rev_sum);
+            size += self.nodes[right].size;
+        }
+        let node = &mut self.nodes[v];
+        node.sum = sum;
+        node.rev_sum = rev_sum;
+        node.size = size;
+    }
+    fn toggle(&mut self, v: usize) {
+        //! 部分木全体を反転させ、子への反転を溜める
+        if v == NIL {
+            return;
+        }
+        let node = &mut self.nodes[v];
+        std::mem::swap(&mut node.left, &mut node.right);
+        std::mem::swap(&mut node.sum, &mut node.rev_sum);
+        node.rev ^= true;
+    }
+    fn apply_at(&mut self, v: usize, a: &<T::L as Monoid>::S) {
+        //! 部分木全体に作用させ、子への作用を溜める
+        if v == NIL {
+            return;
+        }
+        let size = self.nodes[v].size;
+        let node = &mut self.nodes[v];
+        // 作用の失敗には対応しない
+        const MSG: &str = "try_mapping must not fail here";
+        node.val = T::try_mapping(&node.val, a, 1).expect(MSG);
+        node.sum = T::try_mapping(&node.sum, a, size).expect(MSG);
+        node.rev_sum = T::try_mapping(&node.rev_sum, a, size).expect(MSG);
+        node.lazy = T::L::op(&node.lazy, a);
+    }
+    fn propagate_at(&mut self, v: usize) {
+        let (left, right) = (self.nodes[v].left, self.nodes[v].right);
+        if self.nodes[v].rev {
+            self.toggle(left);
+            self.toggle(right);
+            self.nodes[v].rev = false;
+        }
+        let a = std::mem::replace(&mut self.nodes[v].lazy, T::L::e());
+        self.apply_at(left, &a);
+        self.apply_at(right, &a);
+    }
+    fn rotate(&mut self, v: usize) {
+        let p = self.nodes[v].parent;
+        let g = self.nodes[p].parent;
+        let p_is_root = self.is_root(p);
+        if self.nodes[p].left == v {
+            let c = self.nodes[v].right;
+            self.nodes[p].left = c;
+            if c != NIL {
+                self.nodes[c].parent = p;
+            }
+            self.nodes[v].right = p;
+        } else {
+            let c = self.nodes[v].left;
+            self.nodes[p].right = c;
+            if c != NIL {
+                self.nodes[c].parent = p;
+            }
+            self.nodes[v].left = p;
+        }
+        self.nodes[p].parent = v;
+        self.nodes[v].parent = g;
+        if !p_is_root {
+            if self.nodes[g].left == p {
+                self.nodes[g].left = v;
+            } else {
+                self.nodes[g].right = v;
+            }
+        }
+        self.recalc(p);
+        self.recalc(v);
+    }
+    fn splay(&mut self, v: usize) {
+        // 上から遅延を伝播
+        let mut path = vec![v];
+        while !self.is_root(*path.last().unwrap()) {
+            path.push(self.nodes[*path.last().unwrap()].parent);
+        }
+        while let Some(u) = path.pop() {
+            self.propagate_at(u);
+        }
+
+        while !self.is_root(v) {
+            let p = self.nodes[v].parent;
+            if !self.is_root(p) {
+                let g = self.nodes[p].parent;
+                if (self.nodes[g].left == p) == (self.nodes[p].left == v) {
+                    self.rotate(p);
+                } else {
+                    self.rotate(v);
+                }
+            }
+            self.rotate(v);
+        }
+    }
+    fn expose(&mut self, v: usize) -> usize {
+        //! 根から`v`までを一つのsplay木にし、`v`をその根にする
+        //!
+        //! 最後に繋ぎ変えた頂点を返す
+        let mut prev = NIL;
+        let mut cur = v;
+        while cur != NIL {
+            self.splay(cur);
+            self.nodes[cur].right = prev;
+            self.recalc(cur);
+            prev = cur;
+            cur = self.nodes[cur].parent;
+        }
+        self.splay(v);
+        prev
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::LinkCutTree;
+    use crate::{
+        data_structure::implicit_treap::tests::{WeightedSum, WeightedSumAdd},
+        traits::Monoid,
+    };
+
+    // 愚直: rootからの親を求める
+    fn parents(g: &[Vec<usize>], root: usize) -> Vec<Option<usize>> {
+        let mut par = vec![None; g.len()];
+        let mut seen = vec![false; g.len()];
+        let mut stack = vec![root];
+        seen[root] = true;
+        while let Some(v) = stack.pop() {
+            for &nv in &g[v] {
+                if !seen[nv] {
+                    seen[nv] = true;
+                    par[nv] = Some(v);
+                    stack.push(nv);
+                }
+            }
+        }
+        par
+    }
+    fn path(g: &[Vec<usize>], u: usize, v: usize) -> Option<Vec<usize>> {
+        let par = parents(g, u);
+        let mut ret = vec![v];
+        while *ret.last().unwrap() != u {
+            ret.push(par[*ret.last().unwrap()]?);
+        }
+        ret.reverse();
+        Some(ret)
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let n = 12;
+        let mut val = (0..n).map(|_| rng.gen_range(0..10)).collect::<Vec<i64>>();
+        let mut lct = LinkCutTree::<WeightedSumAdd>::build(
+            &val.iter().map(|&x| (x, 0, 1)).collect::<Vec<_>>(),
+        );
+        let mut g = vec![vec![]; n];
+        let mut edges = vec![];
+        for _ in 0..3000 {
+            let u = rng.gen_range(0..n);
+            let v = rng.gen_range(0..n);
+            match rng.gen_range(0..5) {
+                0 => {
+                    if path(&g, u, v).is_none() {
+                        lct.link(u, v);
+                        g[u].push(v);
+                        g[v].push(u);
+                        edges.push((u, v));
+                    }
+                }
+                1 if !edges.is_empty() => {
+                    let (a, b) = edges.swap_remove(rng.gen_range(0..edges.len()));
+                    lct.cut(b, a);
+                    g[a].retain(|&x| x != b);
+                    g[b].retain(|&x| x != a);
+                }
+                2 => {
+                    if let Some(p) = path(&g, u, v) {
+                        let x = rng.gen_range(0..10);
+                        lct.apply_path(u, v, x);
+                        p.iter().for_each(|&w| val[w] += x);
+                    }
+                }
+                3 => {
+                    let r = rng.gen_range(0..n);
+                    lct.evert(r);
+                    let par = parents(&g, r);
+                    let ancestors = |mut w: usize| {
+                        let mut ret = vec![w];
+                        while let Some(p) = par[w] {
+                            ret.push(p);
+                            w = p;
+                        }
+                        ret
+                    };
+                    if path(&g, r, u).is_some() && path(&g, r, v).is_some() {
+                        let au = ancestors(u);
+                        let expected = ancestors(v).into_iter().find(|w| au.contains(w));
+                        assert_eq!(lct.lca(u, v), expected);
+                        assert_eq!(lct.root(u), r);
+                    } else if path(&g, u, v).is_none() {
+                        assert_eq!(lct.lca(u, v), None);
+                    }
+                }
+                _ => {
+                    assert_eq!(lct.is_connected(u, v), path(&g, u, v).is_some());
+                    if let Some(p) = path(&g, u, v) {
+                        let expected = p.iter().fold(WeightedSum::e(), |acc, &w| {
+                            WeightedSum::op(&acc, &(val[w], 0, 1))
+                        });
+                        assert_eq!(lct.fold_path(u, v), expected);
+                    }
+                }
+            }
+        }
+        for (v, &x) in val.iter().enumerate() {
+            assert_eq!(lct.get(v).0, x);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use cargo_snippet::snippet;
+
+/// 各ノードに区間をソートした列を持つセグ木
+///
+/// 構築`O(N log N)`、クエリ`O(log^2 N)`
+#[snippet("MergeSortTree")]
+#[derive(Debug, Clone)]
+pub struct MergeSortTree<T> {
+    original_size: usize,
+    leaf_size: usize,
+    node: Vec<Vec<T>>,
+}
+#[snippet("MergeSortTree")]
+impl<T> MergeSortTree<T>
+where
+    T: Clone + Ord,
+{
+    pub fn new(v: &[T]) -> Self {
+        let size = v.len().next_power_of_two();
+        let mut node = vec![vec![]; 2 * size];
+        for (i, x) in v.iter().enumerate() {
+            node[i + size].push(x.clone());
+        }
+        for i in (1..size).rev() {
+            let (left, right) = (&node[i << 1], &node[i << 1 | 1]);
+            let mut merged = Vec::with_capacity(left.len() + right.len());
+            let (mut a, mut b) = (0, 0);
+            while a < left.len() || b < right.len() {
+                if b == right.len() || (a < left.len() && left[a] <= right[b]) {
+                    merged.push(left[a].clone());
+                    a += 1;
+                } else {
+                    merged.push(right[b].clone());
+                    b += 1;
+                }
+            }
+            node[i] = merged;
+        }
+        MergeSortTree {
+            original_size: v.len(),
+            leaf_size: size,
+            node,
+        }
+    }
+    fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        use std::ops::Bound;
+        let l = match range.start_bound() {
+            Bound::Included(l) => *l,
+            Bound::Excluded(l) => l + 1,
+            Bound::Unbounded => 0,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(r) => r + 1,
+            Bound::Excluded(r) => *r,
+            Bound::Unbounded => r_max,
+        };
+        (l, r)
+    }
+    pub fn count_less<R>(&self, range: R, x: &T) -> usize
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`x`未満の値の個数
+        let (l, r) = Self::range_to_tuple(range, self.original_size);
+        assert!(l <= r && r <= self.original_size);
+        let mut l = l + self.leaf_size;
+        let mut r = r + self.leaf_size;
+        let mut ret = 0;
+        while l < r {
+            if l & 1 == 1 {
+                ret += self.node[l].partition_point(|y| y < x);
+                l += 1;
+            }
+            if r & 1 == 1 {
+                r -= 1;
+                ret += self.node[r].partition_point(|y| y < x);
+            }
+            l >>= 1;
+            r >>= 1;
+        }
+        ret
+    }
+    pub fn range_freq<R>(&self, range: R, lower: &T, upper: &T) -> usize
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`lower <= x < upper`を満たす`x`の個数
+        let (l, r) = Self::range_to_tuple(range, self.original_size);
+        if upper <= lower {
+            return 0;
+        }
+        self.count_less(l..r, upper) - self.count_less(l..r, lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MergeSortTree;
+
+    #[test]
+    fn it_works() {
+        let v = vec![5, 1, 4, 1, 3, 9, 2, 6];
+        let mst = MergeSortTree::new(&v);
+        assert_eq!(mst.count_less(.., &4), 4);
+        assert_eq!(mst.count_less(1..5, &3), 2);
+        assert_eq!(mst.range_freq(2..=6, &2, &9), 3);
+        assert_eq!(mst.range_freq(3..3, &0, &10), 0);
+    }
+}
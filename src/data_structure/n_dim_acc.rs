@@ -1,132 +1,279 @@
-#[cfg(test)]
-mod tests {
+use crate::traits::CommutaitveGroup;
 
-    #[test]
-    fn it_works() {}
+/// 範囲を半開区間`[l, r)`に直す
+fn range_to_tuple<R>(range: &R, r_max: usize) -> (usize, usize)
+where
+    R: std::ops::RangeBounds<usize>,
+{
+    use std::ops::Bound;
+    let l = match range.start_bound() {
+        Bound::Included(l) => *l,
+        Bound::Excluded(l) => l + 1,
+        Bound::Unbounded => 0,
+    };
+    let r = match range.end_bound() {
+        Bound::Included(r) => r + 1,
+        Bound::Excluded(r) => *r,
+        Bound::Unbounded => r_max,
+    };
+    assert!(l <= r && r <= r_max);
+    (l, r)
 }
 
-pub struct Acc3d {
-    acc: Vec<Vec<Vec<isize>>>,
+/// 各次元の長さを1ずつ伸ばした配列の、次元ごとの歩幅と全体の長さ
+fn strides<const D: usize>(shape: &[usize; D]) -> ([usize; D], usize) {
+    let mut stride = [0; D];
+    let mut len = 1;
+    for d in (0..D).rev() {
+        stride[d] = len;
+        len *= shape[d] + 1;
+    }
+    (stride, len)
 }
-impl Acc3d {
-    pub fn new(v: &[Vec<Vec<isize>>]) -> Self {
-        let n = v.len();
-        let m = v[0].len();
-        let w = v[0][0].len();
-        let mut acc = vec![vec![vec![0; w + 1]; m + 1]; n + 1];
-        for i in 1..=n {
-            for j in 1..=m {
-                for k in 1..=w {
-                    acc[i][j][k] = acc[i - 1][j][k] + v[i - 1][j - 1][k - 1]
-                }
-            }
-        }
-        for i in 1..=n {
-            for j in 1..=m {
-                for k in 1..=w {
-                    acc[i][j][k] += acc[i][j - 1][k];
-                }
-            }
-        }
-        for i in 1..=n {
-            for j in 1..=m {
-                for k in 1..=w {
-                    acc[i][j][k] += acc[i][j][k - 1];
-                }
+
+/// `D`次元の累積和
+///
+/// 可換群`T`の上で、超直方体の総積を`O(2^D)`で求める
+pub struct AccNd<T, const D: usize>
+where
+    T: CommutaitveGroup,
+{
+    shape: [usize; D],
+    stride: [usize; D],
+    acc: Vec<T::S>,
+}
+impl<T, const D: usize> AccNd<T, D>
+where
+    T: CommutaitveGroup,
+{
+    pub fn new(v: &[T::S], shape: [usize; D]) -> Self {
+        //! 行優先で平坦化した、形が`shape`の配列`v`から構築する
+        //!
+        //! `O(D * N)`
+        assert_eq!(v.len(), shape.iter().product::<usize>());
+        let (stride, len) = strides(&shape);
+        let mut acc = vec![T::e(); len];
+        for (idx, x) in v.iter().enumerate() {
+            // vでの位置を、先頭に0を挟んだaccでの位置に直す
+            let mut rest = idx;
+            let mut pos = 0;
+            for d in (0..D).rev() {
+                pos += (rest % shape[d] + 1) * stride[d];
+                rest /= shape[d];
             }
+            acc[pos] = x.clone();
         }
-        Acc3d { acc }
+        prefix_fold::<T, D>(&mut acc, &shape, &stride);
+        AccNd { shape, stride, acc }
+    }
+    pub fn shape(&self) -> [usize; D] {
+        self.shape
     }
-    fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
+    pub fn fold<R>(&self, ranges: [R; D]) -> T::S
     where
         R: std::ops::RangeBounds<usize>,
     {
-        use std::ops::Bound;
-        let l = match range.start_bound() {
-            Bound::Included(l) => *l,
-            Bound::Excluded(l) => l + 1,
-            Bound::Unbounded => 0,
-        };
-        let r = match range.end_bound() {
-            Bound::Included(r) => r + 1,
-            Bound::Excluded(r) => *r,
-            Bound::Unbounded => r_max,
-        };
-        (l, r)
+        //! 各次元の範囲の直積の総積を返す
+        //!
+        //! 次元ごとに範囲の型を変えたいときは`(Bound<usize>, Bound<usize>)`を使う
+        //!
+        //! `O(2^D)`
+        let mut lr = [(0, 0); D];
+        for d in 0..D {
+            lr[d] = range_to_tuple(&ranges[d], self.shape[d]);
+        }
+        // 包除原理で、左端を選んだ次元の数が奇数の角は逆元を足す
+        let mut ret = T::e();
+        for mask in 0..1usize << D {
+            let mut pos = 0;
+            for (d, &(l, r)) in lr.iter().enumerate() {
+                pos += if mask >> d & 1 == 1 { l } else { r } * self.stride[d];
+            }
+            let x = &self.acc[pos];
+            ret = if mask.count_ones() % 2 == 0 {
+                T::op(&ret, x)
+            } else {
+                T::op(&ret, &T::inv(x))
+            };
+        }
+        ret
     }
-    pub fn fold<R>(&self, range_i: R, range_j: R, range_k: R) -> isize
+}
+
+pub type Acc2d<T> = AccNd<T, 2>;
+pub type Acc3d<T> = AccNd<T, 3>;
+
+/// `D`次元のimos法
+///
+/// 超直方体への加算をまとめて受け付け、最後に`build`で各点の値を求める
+pub struct ImosNd<T, const D: usize>
+where
+    T: CommutaitveGroup,
+{
+    shape: [usize; D],
+    stride: [usize; D],
+    diff: Vec<T::S>,
+}
+impl<T, const D: usize> ImosNd<T, D>
+where
+    T: CommutaitveGroup,
+{
+    pub fn new(shape: [usize; D]) -> Self {
+        let (stride, len) = strides(&shape);
+        ImosNd {
+            shape,
+            stride,
+            diff: vec![T::e(); len],
+        }
+    }
+    pub fn add<R>(&mut self, ranges: [R; D], x: &T::S)
     where
         R: std::ops::RangeBounds<usize>,
     {
-        let (li, ri) = Self::range_to_tuple(range_i, self.acc.len());
-        let (lj, rj) = Self::range_to_tuple(range_j, self.acc[0].len());
-        let (lk, rk) = Self::range_to_tuple(range_k, self.acc[0][0].len());
-        // self.acc[ri][rj][rk] + self.acc[ri][lj][lk] + self.acc[li][rj][lk] + self.acc[li][lj][rk]
-        //     - self.acc[li][rj][rk]
-        //     - self.acc[ri][lj][rk]
-        //     - self.acc[ri][rj][lk]
-        //     - self.acc[li][lj][lk]
-        let sum_k = |ri: usize, rj: usize, lk: usize, rk: usize| {
-            self.acc[ri][rj][rk] - self.acc[ri][rj][lk]
-        };
-        let sum_jk = |ri: usize, lj: usize, rj: usize, lk: usize, rk: usize| {
-            sum_k(ri, rj, lk, rk) - sum_k(ri, lj, lk, rk)
-        };
-        let sum_ijk = |li: usize, ri: usize, lj: usize, rj: usize, lk: usize, rk: usize| {
-            sum_jk(ri, lj, rj, lk, rk) - sum_jk(li, lj, rj, lk, rk)
-        };
-        sum_ijk(li, ri, lj, rj, lk, rk)
+        //! 各次元の範囲の直積に`x`を作用させる
+        //!
+        //! `O(2^D)`
+        let mut lr = [(0, 0); D];
+        for d in 0..D {
+            lr[d] = range_to_tuple(&ranges[d], self.shape[d]);
+            if lr[d].0 == lr[d].1 {
+                return;
+            }
+        }
+        let inv = T::inv(x);
+        for mask in 0..1usize << D {
+            let mut pos = 0;
+            for (d, &(l, r)) in lr.iter().enumerate() {
+                pos += if mask >> d & 1 == 1 { r } else { l } * self.stride[d];
+            }
+            let y = if mask.count_ones() % 2 == 0 { x } else { &inv };
+            self.diff[pos] = T::op(&self.diff[pos], y);
+        }
+    }
+    pub fn build(&self) -> Vec<T::S> {
+        //! 行優先で平坦化した、形が`shape`の配列を返す
+        //!
+        //! `O(D * N)`
+        let mut acc = self.diff.clone();
+        prefix_fold::<T, D>(&mut acc, &self.shape, &self.stride);
+        acc.into_iter()
+            .enumerate()
+            .filter(|&(pos, _)| {
+                (0..D).all(|d| pos / self.stride[d] % (self.shape[d] + 1) < self.shape[d])
+            })
+            .map(|(_, x)| x)
+            .collect()
     }
 }
-pub struct Acc2d {
-    acc: Vec<Vec<isize>>,
-}
-impl Acc2d {
-    pub fn new(v: &[Vec<isize>]) -> Self {
-        let n = v.len();
-        let m = v[0].len();
-        let mut acc = vec![vec![0; m + 1]; n + 1];
-        for i in 1..=n {
-            for j in 1..=m {
-                acc[i][j] = acc[i - 1][j] + v[i - 1][j - 1];
+
+pub type Imos2d<T> = ImosNd<T, 2>;
+
+/// 各次元の方向に順に累積をとる
+fn prefix_fold<T, const D: usize>(acc: &mut [T::S], shape: &[usize; D], stride: &[usize; D])
+where
+    T: CommutaitveGroup,
+{
+    for d in 0..D {
+        for pos in 0..acc.len() {
+            let c = pos / stride[d] % (shape[d] + 1);
+            if c > 0 {
+                acc[pos] = T::op(&acc[pos - stride[d]], &acc[pos]);
             }
         }
-        for j in 1..=m {
-            for i in 1..=n {
-                acc[i][j] += acc[i][j - 1];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{Commutative, Inv, Monoid};
+    use std::ops::Bound;
+
+    struct Sum;
+    impl Monoid for Sum {
+        type S = isize;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            left + right
+        }
+        fn e() -> Self::S {
+            0
+        }
+    }
+    impl Inv for Sum {
+        fn inv(s: &Self::S) -> Self::S {
+            -s
+        }
+    }
+    impl Commutative for Sum {}
+    impl CommutaitveGroup for Sum {}
+
+    #[test]
+    fn acc() {
+        let (n, m, w) = (3, 4, 5);
+        let v: Vec<isize> = (0..(n * m * w) as isize).map(|x| x * 7 % 11 - 5).collect();
+        let acc = AccNd::<Sum, 3>::new(&v, [n, m, w]);
+        for li in 0..=n {
+            for ri in li..=n {
+                for lj in 0..=m {
+                    for rj in lj..=m {
+                        for lk in 0..=w {
+                            for rk in lk..=w {
+                                let mut sum = 0;
+                                for i in li..ri {
+                                    for j in lj..rj {
+                                        for k in lk..rk {
+                                            sum += v[(i * m + j) * w + k];
+                                        }
+                                    }
+                                }
+                                assert_eq!(acc.fold([li..ri, lj..rj, lk..rk]), sum);
+                            }
+                        }
+                    }
+                }
             }
         }
-        Acc2d { acc }
+        // 長方形の配列で、上限なしの範囲が各次元の長さになる
+        let acc = Acc2d::<Sum>::new(&v[..n * m], [n, m]);
+        assert_eq!(acc.fold([.., ..]), v[..n * m].iter().sum());
+        assert_eq!(
+            acc.fold([1.., 2..]),
+            v[m + 2] + v[m + 3] + v[2 * m + 2] + v[2 * m + 3]
+        );
+        assert_eq!(acc.fold([..=0, ..=0]), v[0]);
     }
-    pub fn fold<R>(&self, range_i: R, range_j: R) -> isize
-    where
-        R: std::ops::RangeBounds<usize>,
-    {
-        // rangeを半開区間に直す
-        // 0..2 -> l = 0, r = 2,
-        // 0..=2 -> l = 0, r = 3,
-        // 0.. -> l = 0, r = もとの配列の長さ+1
-        use std::ops::Bound;
-        let li = match range_i.start_bound() {
-            Bound::Included(l) => *l,
-            Bound::Excluded(l) => l + 1,
-            Bound::Unbounded => 0,
-        };
-        let ri = match range_i.end_bound() {
-            Bound::Included(r) => r + 1,
-            Bound::Excluded(r) => *r,
-            Bound::Unbounded => self.acc.len(),
-        };
-        let lj = match range_j.start_bound() {
-            Bound::Included(l) => *l,
-            Bound::Excluded(l) => l + 1,
-            Bound::Unbounded => 0,
-        };
-        let rj = match range_j.end_bound() {
-            Bound::Included(r) => r + 1,
-            Bound::Excluded(r) => *r,
-            Bound::Unbounded => self.acc.len(),
-        };
-        self.acc[ri][rj] + self.acc[li][lj] - self.acc[ri][lj] - self.acc[li][rj]
+
+    #[test]
+    fn imos() {
+        let (n, m) = (4, 6);
+        let mut imos = ImosNd::<Sum, 2>::new([n, m]);
+        let mut naive = vec![0; n * m];
+        let queries = [
+            (0..2, 1..4, 3),
+            (1..4, 0..6, -2),
+            (2..3, 5..6, 7),
+            (0..4, 3..3, 100),
+        ];
+        for (ri, rj, x) in queries {
+            for i in ri.clone() {
+                for j in rj.clone() {
+                    naive[i * m + j] += x;
+                }
+            }
+            imos.add([ri, rj], &x);
+        }
+        assert_eq!(imos.build(), naive);
+        let mut imos = Imos2d::<Sum>::new([n, m]);
+        imos.add(
+            [
+                (Bound::Included(1), Bound::Unbounded),
+                (Bound::Unbounded, Bound::Included(2)),
+            ],
+            &1,
+        );
+        let v = imos.build();
+        assert_eq!(v.iter().sum::<isize>(), 9);
+        assert_eq!(v[m], 1);
+        assert_eq!(v[m + 3], 0);
     }
 }
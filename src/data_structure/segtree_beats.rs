@@ -0,0 +1,329 @@
+use cargo_snippet::snippet;
+
+use crate::{
+    data_structure::lazy_segtree::{ForLazySegtree, LazySegtree},
+    traits::Monoid,
+};
+
+/// Segment Tree Beats
+///
+/// `try_mapping`が失敗したノードは子に再帰する`LazySegtree`
+///
+/// 区間作用は ならし`O(log^2 N)`
+#[snippet("SegtreeBeats")]
+#[snippet(include = "LazySegtree")]
+pub type SegtreeBeats = LazySegtree<ChminChmaxAdd>;
+
+#[snippet("SegtreeBeats")]
+const BEATS_INF: i64 = i64::MAX / 4;
+
+/// 区間chmin, chmax, 加算, 区間和, 最小値, 最大値を扱うノード
+#[snippet("SegtreeBeats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChminChmaxAddNode {
+    pub sum: i64,
+    pub max: i64,
+    max2: i64,
+    max_cnt: i64,
+    pub min: i64,
+    min2: i64,
+    min_cnt: i64,
+    len: i64,
+}
+#[snippet("SegtreeBeats")]
+impl ChminChmaxAddNode {
+    pub fn new(x: i64) -> Self {
+        ChminChmaxAddNode {
+            sum: x,
+            max: x,
+            max2: -BEATS_INF,
+            max_cnt: 1,
+            min: x,
+            min2: BEATS_INF,
+            min_cnt: 1,
+            len: 1,
+        }
+    }
+    fn add(&mut self, a: i64) {
+        self.sum += a * self.len;
+        self.max += a;
+        if self.max2 != -BEATS_INF {
+            self.max2 += a;
+        }
+        self.min += a;
+        if self.min2 != BEATS_INF {
+            self.min2 += a;
+        }
+    }
+    // min < x < min2を仮定
+    fn chmax(&mut self, x: i64) {
+        self.sum += (x - self.min) * self.min_cnt;
+        if self.max == self.min {
+            self.max = x;
+        } else if self.max2 == self.min {
+            self.max2 = x;
+        }
+        self.min = x;
+    }
+    // max2 < x < maxを仮定
+    fn chmin(&mut self, x: i64) {
+        self.sum += (x - self.max) * self.max_cnt;
+        if self.min == self.max {
+            self.min = x;
+        } else if self.min2 == self.max {
+            self.min2 = x;
+        }
+        self.max = x;
+    }
+}
+
+/// `x -> min(max(x + add, lo), hi)`を表す作用
+#[snippet("SegtreeBeats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChminChmaxAddLazy {
+    add: i64,
+    lo: i64,
+    hi: i64,
+}
+
+#[snippet("SegtreeBeats")]
+pub struct ChminChmaxAddM;
+#[snippet("SegtreeBeats")]
+impl Monoid for ChminChmaxAddM {
+    type S = ChminChmaxAddNode;
+    fn op(left: &Self::S, right: &Self::S) -> Self::S {
+        let (max, max2, max_cnt) = match left.max.cmp(&right.max) {
+            std::cmp::Ordering::Greater => (left.max, left.max2.max(right.max), left.max_cnt),
+            std::cmp::Ordering::Less => (right.max, right.max2.max(left.max), right.max_cnt),
+            std::cmp::Ordering::Equal => (
+                left.max,
+                left.max2.max(right.max2),
+                left.max_cnt + right.max_cnt,
+            ),
+        };
+        let (min, min2, min_cnt) = match left.min.cmp(&right.min) {
+            std::cmp::Ordering::Less => (left.min, left.min2.min(right.min), left.min_cnt),
+            std::cmp::Ordering::Greater => (right.min, right.min2.min(left.min), right.min_cnt),
+            std::cmp::Ordering::Equal => (
+                left.min,
+                left.min2.min(right.min2),
+                left.min_cnt + right.min_cnt,
+            ),
+        };
+        ChminChmaxAddNode {
+            sum: left.sum + right.sum,
+            max,
+            max2,
+            max_cnt,
+            min,
+            min2,
+            min_cnt,
+            len: left.len + right.len,
+        }
+    }
+    fn e() -> Self::S {
+        ChminChmaxAddNode {
+            sum: 0,
+            max: -BEATS_INF,
+            max2: -BEATS_INF,
+            max_cnt: 0,
+            min: BEATS_INF,
+            min2: BEATS_INF,
+            min_cnt: 0,
+            len: 0,
+        }
+    }
+}
+
+#[snippet("SegtreeBeats")]
+pub struct ChminChmaxAddL;
+#[snippet("SegtreeBeats")]
+impl Monoid for ChminChmaxAddL {
+    type S = ChminChmaxAddLazy;
+    fn op(left: &Self::S, right: &Self::S) -> Self::S {
+        //! `left`を作用させてから`right`を作用させる
+        let shift = |x: i64| {
+            if x.abs() == BEATS_INF {
+                x
+            } else {
+                x + right.add
+            }
+        };
+        let lo = shift(left.lo).max(right.lo).min(right.hi);
+        let hi = shift(left.hi).max(right.lo).min(right.hi);
+        ChminChmaxAddLazy {
+            add: left.add + right.add,
+            lo,
+            hi,
+        }
+    }
+    fn e() -> Self::S {
+        ChminChmaxAddLazy {
+            add: 0,
+            lo: -BEATS_INF,
+            hi: BEATS_INF,
+        }
+    }
+}
+
+/// 区間chmin, 区間chmax, 区間加算, 区間和, 区間最小値, 区間最大値
+#[snippet("SegtreeBeats")]
+pub struct ChminChmaxAdd;
+#[snippet("SegtreeBeats")]
+impl ForLazySegtree for ChminChmaxAdd {
+    type M = ChminChmaxAddM;
+    type L = ChminChmaxAddL;
+    fn mapping(m: &ChminChmaxAddNode, l: &ChminChmaxAddLazy) -> ChminChmaxAddNode {
+        if m.len == 0 {
+            return *m;
+        }
+        // 葉
+        ChminChmaxAddNode::new((m.sum + l.add).max(l.lo).min(l.hi))
+    }
+    fn try_mapping(
+        m: &ChminChmaxAddNode,
+        l: &ChminChmaxAddLazy,
+        _len: usize,
+    ) -> Option<ChminChmaxAddNode> {
+        if m.len == 0 {
+            return Some(*m);
+        }
+        let mut m = *m;
+        if l.add != 0 {
+            m.add(l.add);
+        }
+        if m.min < l.lo {
+            if m.min2 <= l.lo {
+                return None;
+            }
+            m.chmax(l.lo);
+        }
+        if l.hi < m.max {
+            if l.hi <= m.max2 {
+                return None;
+            }
+            m.chmin(l.hi);
+        }
+        Some(m)
+    }
+}
+
+#[snippet("SegtreeBeats")]
+impl SegtreeBeats {
+    pub fn from_vec(v: &[i64]) -> Self {
+        let mut seg = Self::new(v.len());
+        let v = v
+            .iter()
+            .map(|&x| ChminChmaxAddNode::new(x))
+            .collect::<Vec<_>>();
+        seg.build(&v);
+        seg
+    }
+    pub fn chmin<R>(&mut self, range: R, x: i64)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間の各要素を`min(a_i, x)`にする
+        self.apply_range(
+            range,
+            ChminChmaxAddLazy {
+                add: 0,
+                lo: -BEATS_INF,
+                hi: x,
+            },
+        );
+    }
+    pub fn chmax<R>(&mut self, range: R, x: i64)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間の各要素を`max(a_i, x)`にする
+        self.apply_range(
+            range,
+            ChminChmaxAddLazy {
+                add: 0,
+                lo: x,
+                hi: BEATS_INF,
+            },
+        );
+    }
+    pub fn add<R>(&mut self, range: R, x: i64)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間の各要素に`x`を加える
+        self.apply_range(
+            range,
+            ChminChmaxAddLazy {
+                add: x,
+                lo: -BEATS_INF,
+                hi: BEATS_INF,
+            },
+        );
+    }
+    pub fn sum<R>(&mut self, range: R) -> i64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        self.fold(range).sum
+    }
+    pub fn min<R>(&mut self, range: R) -> i64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 空区間なら`i64::MAX / 4`
+        self.fold(range).min
+    }
+    pub fn max<R>(&mut self, range: R) -> i64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 空区間なら`-i64::MAX / 4`
+        self.fold(range).max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::SegtreeBeats;
+
+    #[test]
+    fn random_chmin_chmax_add() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..50 {
+            let n = rng.gen_range(1..=20);
+            let mut v = (0..n)
+                .map(|_| rng.gen_range(-50..=50))
+                .collect::<Vec<i64>>();
+            let mut seg = SegtreeBeats::from_vec(&v);
+            for _ in 0..200 {
+                let l = rng.gen_range(0..=n);
+                let r = rng.gen_range(l..=n);
+                let x = rng.gen_range(-50..=50);
+                match rng.gen_range(0..4) {
+                    0 => {
+                        seg.chmin(l..r, x);
+                        v[l..r].iter_mut().for_each(|a| *a = (*a).min(x));
+                    }
+                    1 => {
+                        seg.chmax(l..r, x);
+                        v[l..r].iter_mut().for_each(|a| *a = (*a).max(x));
+                    }
+                    2 => {
+                        seg.add(l..r, x);
+                        v[l..r].iter_mut().for_each(|a| *a += x);
+                    }
+                    _ => {
+                        assert_eq!(seg.sum(l..r), v[l..r].iter().sum::<i64>());
+                        if l < r {
+                            assert_eq!(seg.min(l..r), *v[l..r].iter().min().unwrap());
+                            assert_eq!(seg.max(l..r), *v[l..r].iter().max().unwrap());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use cargo_snippet::snippet;
+
+use crate::{data_structure::swag::Swag, traits::Monoid};
+
+/// 長さ`k`の各窓の総積を、左端の昇順に返すイテレータ
+#[snippet("sliding_window_fold")]
+pub struct SlidingWindowFold<'a, T>
+where
+    T: Monoid,
+{
+    v: &'a [T::S],
+    k: usize,
+    l: usize,
+    swag: Swag<T>,
+}
+#[snippet("sliding_window_fold")]
+impl<T> Iterator for SlidingWindowFold<'_, T>
+where
+    T: Monoid,
+{
+    type Item = T::S;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.l + self.k > self.v.len() {
+            return None;
+        }
+        if self.l == 0 {
+            for x in &self.v[..self.k] {
+                self.swag.push_back(x.clone());
+            }
+        } else {
+            self.swag.pop_front();
+            self.swag.push_back(self.v[self.l + self.k - 1].clone());
+        }
+        self.l += 1;
+        Some(self.swag.fold())
+    }
+}
+
+#[snippet]
+#[snippet(include = "Swag")]
+pub fn sliding_window_fold<T>(v: &[T::S], k: usize) -> SlidingWindowFold<'_, T>
+where
+    T: Monoid,
+{
+    //! `v[i..i + k]`の総積を`i`の昇順に返す
+    //!
+    //! 全体で`O(N)`
+    assert!(0 < k);
+    SlidingWindowFold {
+        v,
+        k,
+        l: 0,
+        swag: Swag::new(),
+    }
+}
+
+#[snippet]
+#[snippet(include = "Swag")]
+pub fn two_pointers_max_right<T, F>(v: &[T::S], f: F) -> Vec<usize>
+where
+    T: Monoid,
+    F: Fn(&T::S) -> bool,
+{
+    //! 各`l`について、`f(v[l..r]の総積) == true`となる最大の`r`を返す
+    //!
+    //! `f`は区間を縮めてもtrueのままであること
+    //!
+    //! 全体で`O(N)`
+    let n = v.len();
+    let mut ret = Vec::with_capacity(n);
+    let mut swag = Swag::<T>::new();
+    let mut r = 0;
+    for l in 0..n {
+        if r < l {
+            r = l;
+        }
+        while r < n {
+            swag.push_back(v[r].clone());
+            if !f(&swag.fold()) {
+                swag.pop_back();
+                break;
+            }
+            r += 1;
+        }
+        ret.push(r);
+        if l < r {
+            swag.pop_front();
+        }
+    }
+    ret
+}
+
+/// 長さ`k`の各窓で`better`について最良の値を、左端の昇順に返すイテレータ
+///
+/// 値は複製せず、参照を返す
+#[snippet("MonotoneWindow")]
+pub struct MonotoneWindow<'a, T, F>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    v: &'a [T],
+    k: usize,
+    l: usize,
+    r: usize,
+    better: F,
+    deque: std::collections::VecDeque<usize>,
+}
+#[snippet("MonotoneWindow")]
+impl<'a, T, F> Iterator for MonotoneWindow<'a, T, F>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.l + self.k > self.v.len() {
+            return None;
+        }
+        while self.r < self.l + self.k {
+            // 新しい値より良くない値は、今後も最良にならない
+            while let Some(&i) = self.deque.back() {
+                if (self.better)(&self.v[i], &self.v[self.r]) {
+                    break;
+                }
+                self.deque.pop_back();
+            }
+            self.deque.push_back(self.r);
+            self.r += 1;
+        }
+        while self.deque.front().is_some_and(|&i| i < self.l) {
+            self.deque.pop_front();
+        }
+        self.l += 1;
+        self.deque.front().map(|&i| &self.v[i])
+    }
+}
+
+#[snippet]
+#[snippet(include = "MonotoneWindow")]
+pub fn sliding_window_min<T: Ord>(v: &[T], k: usize) -> MonotoneWindow<'_, T, fn(&T, &T) -> bool> {
+    //! `v[i..i + k]`の最小値を`i`の昇順に返す
+    //!
+    //! 全体で`O(N)`
+    assert!(0 < k);
+    MonotoneWindow {
+        v,
+        k,
+        l: 0,
+        r: 0,
+        better: |a, b| a < b,
+        deque: std::collections::VecDeque::new(),
+    }
+}
+
+#[snippet]
+#[snippet(include = "MonotoneWindow")]
+pub fn sliding_window_max<T: Ord>(v: &[T], k: usize) -> MonotoneWindow<'_, T, fn(&T, &T) -> bool> {
+    //! `v[i..i + k]`の最大値を`i`の昇順に返す
+    //!
+    //! 全体で`O(N)`
+    assert!(0 < k);
+    MonotoneWindow {
+        v,
+        k,
+        l: 0,
+        r: 0,
+        better: |a, b| a > b,
+        deque: std::collections::VecDeque::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Add;
+    impl Monoid for Add {
+        type S = i64;
+        fn op(a: &Self::S, b: &Self::S) -> Self::S {
+            a + b
+        }
+        fn e() -> Self::S {
+            0
+        }
+    }
+
+    #[test]
+    fn sliding_window() {
+        let v = vec![3i64, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        for k in 1..=v.len() {
+            let sums: Vec<i64> = sliding_window_fold::<Add>(&v, k).collect();
+            let mins: Vec<i64> = sliding_window_min(&v, k).copied().collect();
+            let maxs: Vec<i64> = sliding_window_max(&v, k).copied().collect();
+            let windows = v.windows(k);
+            assert_eq!(
+                sums,
+                windows.clone().map(|w| w.iter().sum()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                mins,
+                windows
+                    .clone()
+                    .map(|w| *w.iter().min().unwrap())
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                maxs,
+                windows
+                    .map(|w| *w.iter().max().unwrap())
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(sliding_window_fold::<Add>(&v, v.len() + 1).count(), 0);
+    }
+
+    #[test]
+    fn two_pointers() {
+        let v = vec![3i64, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5];
+        for lim in 0..20 {
+            let ret = two_pointers_max_right::<Add, _>(&v, |&s| s <= lim);
+            for l in 0..v.len() {
+                let mut r = l;
+                while r < v.len() && v[l..=r].iter().sum::<i64>() <= lim {
+                    r += 1;
+                }
+                assert_eq!(ret[l], r);
+            }
+        }
+    }
+}
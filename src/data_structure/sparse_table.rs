@@ -0,0 +1,188 @@
+use cargo_snippet::snippet;
+
+use crate::traits::{Idempotent, Monoid};
+
+#[snippet("SparseTable")]
+#[snippet("DisjointSparseTable")]
+fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
+where
+    R: std::ops::RangeBounds<usize>,
+{
+    use std::ops::Bound;
+    let l = match range.start_bound() {
+        Bound::Included(l) => *l,
+        Bound::Excluded(l) => l + 1,
+        Bound::Unbounded => 0,
+    };
+    let r = match range.end_bound() {
+        Bound::Included(r) => r + 1,
+        Bound::Excluded(r) => *r,
+        Bound::Unbounded => r_max,
+    };
+    (l, r)
+}
+
+/// 冪等なモノイドの、静的な配列に対する区間積
+///
+/// 構築`O(N log N)`、クエリ`O(1)`
+#[snippet("SparseTable")]
+pub struct SparseTable<T>
+where
+    T: Monoid + Idempotent,
+{
+    /// `table[k][i]`は`v[i..i + 2^k]`の総積
+    table: Vec<Vec<T::S>>,
+}
+#[snippet("SparseTable")]
+impl<T> SparseTable<T>
+where
+    T: Monoid + Idempotent,
+{
+    pub fn new(v: &[T::S]) -> Self {
+        let mut table = vec![v.to_vec()];
+        let mut k = 1;
+        while 1 << k <= v.len() {
+            let prev = &table[k - 1];
+            let half = 1 << (k - 1);
+            let next = (0..=v.len() - (1 << k))
+                .map(|i| T::op(&prev[i], &prev[i + half]))
+                .collect();
+            table.push(next);
+            k += 1;
+        }
+        SparseTable { table }
+    }
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn fold<R>(&self, range: R) -> T::S
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間積 O(1)
+        let (l, r) = range_to_tuple(range, self.len());
+        assert!(l <= r && r <= self.len());
+        if l == r {
+            return T::e();
+        }
+        // 重なる2区間で全体を覆う
+        let k = (r - l).ilog2() as usize;
+        T::op(&self.table[k][l], &self.table[k][r - (1 << k)])
+    }
+}
+
+/// 任意のモノイドの、静的な配列に対する区間積
+///
+/// 構築`O(N log N)`、クエリ`O(1)`
+#[snippet("DisjointSparseTable")]
+pub struct DisjointSparseTable<T>
+where
+    T: Monoid,
+{
+    /// `table[k]`では、`2^k`の奇数倍の位置`m`を境に、
+    /// 左側に`v[i..m]`、右側に`v[m..=i]`の総積を持つ
+    table: Vec<Vec<T::S>>,
+}
+#[snippet("DisjointSparseTable")]
+impl<T> DisjointSparseTable<T>
+where
+    T: Monoid,
+{
+    pub fn new(v: &[T::S]) -> Self {
+        let n = v.len();
+        let levels = if n <= 1 {
+            1
+        } else {
+            (n - 1).ilog2() as usize + 1
+        };
+        let mut table = vec![v.to_vec(); levels];
+        for (k, row) in table.iter_mut().enumerate() {
+            let w = 1 << k;
+            for m in (w..n).step_by(2 * w) {
+                for i in (m - w..m - 1).rev() {
+                    row[i] = T::op(&v[i], &row[i + 1]);
+                }
+                for i in m + 1..n.min(m + w) {
+                    row[i] = T::op(&row[i - 1], &v[i]);
+                }
+            }
+        }
+        DisjointSparseTable { table }
+    }
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn fold<R>(&self, range: R) -> T::S
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間積 O(1)
+        let (l, r) = range_to_tuple(range, self.len());
+        assert!(l <= r && r <= self.len());
+        if l == r {
+            return T::e();
+        }
+        if l + 1 == r {
+            return self.table[0][l].clone();
+        }
+        // lとr - 1が初めて異なるビットの段で、境界をまたいでいる
+        let k = (l ^ (r - 1)).ilog2() as usize;
+        T::op(&self.table[k][l], &self.table[k][r - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Min;
+    impl Monoid for Min {
+        type S = i64;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            *left.min(right)
+        }
+        fn e() -> Self::S {
+            i64::MAX
+        }
+    }
+    impl Idempotent for Min {}
+
+    struct Concat;
+    impl Monoid for Concat {
+        type S = String;
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            format!("{}{}", left, right)
+        }
+        fn e() -> Self::S {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn sparse_table() {
+        for n in 0..20 {
+            let v: Vec<i64> = (0..n).map(|i| (i * 37 + 11) % 17).collect();
+            let s: Vec<String> = (0..n)
+                .map(|i| ((b'a' + i as u8) as char).to_string())
+                .collect();
+            let st = SparseTable::<Min>::new(&v);
+            let dst = DisjointSparseTable::<Concat>::new(&s);
+            assert_eq!(st.len(), n as usize);
+            for l in 0..=n as usize {
+                for r in l..=n as usize {
+                    let min = v[l..r].iter().copied().min().unwrap_or(i64::MAX);
+                    assert_eq!(st.fold(l..r), min);
+                    assert_eq!(dst.fold(l..r), s[l..r].concat());
+                }
+            }
+            assert_eq!(st.fold(..), v.iter().copied().min().unwrap_or(i64::MAX));
+            assert_eq!(dst.fold(..), s.concat());
+        }
+    }
+}
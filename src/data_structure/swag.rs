@@ -1,20 +1,22 @@
-// use cargo_snippet::snippet;
+use cargo_snippet::snippet;
 
 use crate::traits::Monoid;
 
-// #[snippet("Swag")]
+#[snippet("Swag")]
 /// 非可換な演算もできるswag
+///
+/// 両端への追加、削除が ならし`O(1)`
 #[derive(Debug)]
 pub struct Swag<T>
 where
     T: Monoid,
 {
-    front: Vec<T::S>,
-    back: Vec<T::S>,
-    back_raw: Vec<T::S>,
+    // (値, 先頭からその値までの総積) 末尾が先頭の要素
+    front: Vec<(T::S, T::S)>,
+    // (値, その値から末尾までの総積) 末尾が末尾の要素
+    back: Vec<(T::S, T::S)>,
 }
-// #[snippet("Swag")]
-
+#[snippet("Swag")]
 impl<T> Swag<T>
 where
     T: Monoid,
@@ -23,62 +25,94 @@ where
         Swag {
             front: vec![],
             back: vec![],
-            back_raw: vec![],
         }
     }
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
     fn add_front(&mut self, x: T::S) {
-        if self.front.is_empty() {
-            self.front.push(x);
-        } else {
-            let vi = &self.front[self.front.len() - 1];
-            self.front.push(T::op(&x, &vi));
-        }
+        let agg = match self.front.last() {
+            Some((_, vi)) => T::op(&x, vi),
+            None => x.clone(),
+        };
+        self.front.push((x, agg));
     }
     fn add_back(&mut self, x: T::S) {
-        if self.back.is_empty() {
-            self.back.push(x);
+        let agg = match self.back.last() {
+            Some((_, vi)) => T::op(vi, &x),
+            None => x.clone(),
+        };
+        self.back.push((x, agg));
+    }
+    fn rebalance(&mut self, to_front: bool) {
+        //! 空になった側に、もう一方の半分を移す
+        let mut v = self
+            .front
+            .drain(..)
+            .rev()
+            .chain(self.back.drain(..))
+            .map(|(x, _)| x)
+            .collect::<Vec<T::S>>();
+        // to_frontなら先頭側に多めに残す
+        let mid = if to_front {
+            v.len().div_ceil(2)
         } else {
-            let vi = &self.back[self.back.len() - 1];
-            self.back.push(T::op(&vi, &x));
+            v.len() / 2
+        };
+        let back = v.split_off(mid);
+        for x in v.into_iter().rev() {
+            self.add_front(x);
         }
-    }
-    fn b_to_f(&mut self) {
-        if self.front.is_empty() {
-            while self.back.pop().is_some() {
-                let ri = self.back_raw.pop().unwrap();
-                self.add_front(ri);
-            }
+        for x in back {
+            self.add_back(x);
         }
     }
+    pub fn push_front(&mut self, x: T::S) {
+        self.add_front(x);
+    }
     pub fn push_back(&mut self, x: T::S) {
-        self.add_back(x.clone());
-        self.back_raw.push(x);
+        self.add_back(x);
     }
     pub fn pop_front(&mut self) -> Option<T::S> {
-        self.b_to_f();
-        self.front.pop()
-    }
-    pub fn fold(&mut self) -> T::S {
-        if self.front.is_empty() && self.back.is_empty() {
-            return T::e();
-        }
         if self.front.is_empty() {
-            return self.back[self.back.len() - 1].clone();
+            self.rebalance(true);
         }
+        self.front.pop().map(|(x, _)| x)
+    }
+    pub fn pop_back(&mut self) -> Option<T::S> {
         if self.back.is_empty() {
-            return self.front[self.front.len() - 1].clone();
+            self.rebalance(false);
+        }
+        self.back.pop().map(|(x, _)| x)
+    }
+    pub fn front(&self) -> Option<&T::S> {
+        self.front
+            .last()
+            .or_else(|| self.back.first())
+            .map(|(x, _)| x)
+    }
+    pub fn back(&self) -> Option<&T::S> {
+        self.back
+            .last()
+            .or_else(|| self.front.first())
+            .map(|(x, _)| x)
+    }
+    pub fn fold(&self) -> T::S {
+        match (self.front.last(), self.back.last()) {
+            (None, None) => T::e(),
+            (Some((_, f)), None) => f.clone(),
+            (None, Some((_, b))) => b.clone(),
+            (Some((_, f)), Some((_, b))) => T::op(f, b),
         }
-        // self.front[self.front.len() - 1].op(&self.back[self.back.len() - 1])
-        T::op(
-            &self.front[self.front.len() - 1],
-            &self.back[self.back.len() - 1],
-        )
     }
 }
-// #[snippet("Swag")]
+#[snippet("Swag")]
 impl<T> Default for Swag<T>
 where
-    T: Monoid + Clone,
+    T: Monoid,
 {
     fn default() -> Self {
         Self::new()
@@ -99,7 +133,7 @@ mod tests {
                 0
             }
             fn op(left: &Self::S, right: &Self::S) -> Self::S {
-                left.min(right).clone()
+                *left.min(right)
             }
         }
         let mut swag = Swag::<Min>::new();
@@ -108,7 +142,6 @@ mod tests {
             swag.push_back(i);
             println!("{:?}", &swag.front);
             println!("{:?}", &swag.back);
-            println!("{:?}", &swag.back_raw);
             println!("{}", swag.fold());
         }
         println!();
@@ -130,4 +163,44 @@ mod tests {
         assert_eq!(Some(1), swag.pop_front());
         assert_eq!(2, swag.fold());
     }
+
+    #[test]
+    fn deque() {
+        use rand::Rng;
+        use std::collections::VecDeque;
+
+        // 文字列の連結(非可換)
+        struct Concat;
+        impl Monoid for Concat {
+            type S = String;
+            fn e() -> Self::S {
+                String::new()
+            }
+            fn op(left: &Self::S, right: &Self::S) -> Self::S {
+                format!("{}{}", left, right)
+            }
+        }
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let mut swag = Swag::<Concat>::new();
+        let mut deque = VecDeque::new();
+        for _ in 0..1000 {
+            let x = ((b'a' + rng.gen_range(0..26)) as char).to_string();
+            match rng.gen_range(0..4) {
+                0 => {
+                    swag.push_front(x.clone());
+                    deque.push_front(x);
+                }
+                1 => {
+                    swag.push_back(x.clone());
+                    deque.push_back(x);
+                }
+                2 => assert_eq!(swag.pop_front(), deque.pop_front()),
+                _ => assert_eq!(swag.pop_back(), deque.pop_back()),
+            }
+            assert_eq!(swag.len(), deque.len());
+            assert_eq!(swag.front(), deque.front());
+            assert_eq!(swag.back(), deque.back());
+            assert_eq!(swag.fold(), deque.iter().cloned().collect::<String>());
+        }
+    }
 }
@@ -1,30 +1,517 @@
-#![allow(unused)]
-use std::{
-    cell::RefCell,
-    rc::{self, Rc, Weak},
-};
+use cargo_snippet::snippet;
 
+#[snippet("Treap")]
 pub trait SortedSet<T> {
     fn size(&self) -> usize;
     fn add(&mut self, x: T) -> bool;
     fn remove(&mut self, x: T) -> Option<T>;
     fn find(&self, x: T) -> Option<T>;
 }
+
+#[snippet("Treap")]
+const NIL: usize = usize::MAX;
+
+#[snippet("Treap")]
 #[derive(Debug, Clone)]
-pub struct TreapNode<T> {
+struct TreapNode<T> {
     val: T,
-    p: usize,
-    left: RefCell<Option<Rc<TreapNode<T>>>>,
-    right: RefCell<Option<Rc<TreapNode<T>>>>,
-    parent: RefCell<Option<Weak<TreapNode<T>>>>,
+    priority: u64,
+    left: usize,
+    right: usize,
+    size: usize,
 }
+
+/// ノードの置き場。`split`で分けた`Treap`どうしで共有する
+#[snippet("Treap")]
+#[derive(Debug, Clone)]
+struct TreapArena<T> {
+    nodes: Vec<TreapNode<T>>,
+    free: Vec<usize>,
+    seed: u64,
+}
+
+/// 順序付き集合、多重集合
+///
+/// ノードは配列で管理し、子は添字で持つ。`split`で分けたものは同じ配列を共有する
+///
+/// 各操作 期待`O(log N)`
+#[snippet("Treap")]
+#[derive(Debug)]
 pub struct Treap<T> {
-    root: Option<Rc<TreapNode<T>>>,
-    size: usize,
+    arena: std::rc::Rc<std::cell::RefCell<TreapArena<T>>>,
+    root: usize,
+    multi: bool,
+}
+#[snippet("Treap")]
+impl<T> Treap<T>
+where
+    T: Clone + Ord,
+{
+    pub fn new() -> Self {
+        //! 重複を許さない集合
+        Treap {
+            arena: std::rc::Rc::new(std::cell::RefCell::new(TreapArena {
+                nodes: vec![],
+                free: vec![],
+                seed: 88172645463325252,
+            })),
+            root: NIL,
+            multi: false,
+        }
+    }
+    pub fn new_multi() -> Self {
+        //! 重複を許す多重集合
+        let mut ret = Self::new();
+        ret.multi = true;
+        ret
+    }
+    pub fn len(&self) -> usize {
+        self.arena.borrow().size_of(self.root)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.root == NIL
+    }
+    pub fn contains(&self, x: &T) -> bool {
+        self.lower_bound(x).is_some_and(|y| y == *x)
+    }
+    pub fn count(&self, x: &T) -> usize {
+        //! `x`の個数
+        let arena = self.arena.borrow();
+        arena.rank_by(self.root, |y| y <= x) - arena.rank_by(self.root, |y| y < x)
+    }
+    pub fn lower_bound(&self, x: &T) -> Option<T> {
+        //! `x`以上の最小の要素
+        self.arena.borrow().first_by(self.root, |y| x <= y)
+    }
+    pub fn upper_bound(&self, x: &T) -> Option<T> {
+        //! `x`より大きい最小の要素
+        self.arena.borrow().first_by(self.root, |y| x < y)
+    }
+    pub fn rank(&self, x: &T) -> usize {
+        //! `x`未満の要素の個数
+        self.arena.borrow().rank_by(self.root, |y| y < x)
+    }
+    pub fn kth(&self, k: usize) -> Option<T> {
+        //! `k`番目(0-indexed)に小さい要素
+        let arena = self.arena.borrow();
+        let mut t = self.root;
+        let mut k = k;
+        while t != NIL {
+            let ls = arena.size_of(arena.nodes[t].left);
+            match k.cmp(&ls) {
+                std::cmp::Ordering::Less => t = arena.nodes[t].left,
+                std::cmp::Ordering::Equal => return Some(arena.nodes[t].val.clone()),
+                std::cmp::Ordering::Greater => {
+                    k -= ls + 1;
+                    t = arena.nodes[t].right;
+                }
+            }
+        }
+        None
+    }
+    pub fn split(&mut self, x: &T) -> Self {
+        //! `x`以上の要素を取り出して返す
+        //!
+        //! 返り値はノードの配列を共有する。期待`O(log N)`
+        let (l, r) = self.arena.borrow_mut().split_by(self.root, &|y| y < x);
+        self.root = l;
+        Treap {
+            arena: self.arena.clone(),
+            root: r,
+            multi: self.multi,
+        }
+    }
+    pub fn merge(&mut self, mut other: Self) {
+        //! `other`の要素を全て加える。`self`の要素は全て`other`の要素以下であること
+        //!
+        //! `other`が`split`で分けたもの(配列を共有する)なら期待`O(log N)`
+        //!
+        //! そうでなければ`other`のノードを移すので`O(log N + otherの要素数)`
+        if let (Some(a), Some(b)) = (self.kth(self.len().wrapping_sub(1)), other.kth(0)) {
+            assert!(if self.multi { a <= b } else { a < b });
+        }
+        let r = std::mem::replace(&mut other.root, NIL);
+        let r = if std::rc::Rc::ptr_eq(&self.arena, &other.arena) {
+            r
+        } else {
+            self.arena
+                .borrow_mut()
+                .copy_subtree(&other.arena.borrow(), r)
+        };
+        let mut arena = self.arena.borrow_mut();
+        self.root = arena.merge_node(self.root, r);
+    }
+    pub fn iter(&self) -> TreapIter<'_, T> {
+        //! 昇順に列挙する
+        let mut iter = TreapIter {
+            treap: self,
+            stack: vec![],
+        };
+        iter.push_left(self.root);
+        iter
+    }
 }
-impl<T> Treap<T> {
-    fn rotate_right(u: TreapNode<T>) {
-        let mut w = u.right.borrow_mut().take().unwrap();
-        *w.parent.borrow_mut() = u.parent.borrow_mut().take();
+
+#[snippet("Treap")]
+impl<T> TreapArena<T>
+where
+    T: Clone + Ord,
+{
+    fn next_priority(&mut self) -> u64 {
+        //! xorshift
+        self.seed ^= self.seed << 7;
+        self.seed ^= self.seed >> 9;
+        self.seed
+    }
+    fn size_of(&self, t: usize) -> usize {
+        if t == NIL {
+            0
+        } else {
+            self.nodes[t].size
+        }
+    }
+    fn recalc(&mut self, t: usize) {
+        self.nodes[t].size =
+            1 + self.size_of(self.nodes[t].left) + self.size_of(self.nodes[t].right);
+    }
+    fn new_node(&mut self, x: T) -> usize {
+        let node = TreapNode {
+            val: x,
+            priority: self.next_priority(),
+            left: NIL,
+            right: NIL,
+            size: 1,
+        };
+        if let Some(i) = self.free.pop() {
+            self.nodes[i] = node;
+            i
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+    fn split_by<F>(&mut self, t: usize, go_left: &F) -> (usize, usize)
+    where
+        F: Fn(&T) -> bool,
+    {
+        //! `go_left`を満たす要素とそれ以外に分割する。`go_left`は単調であること
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        if go_left(&self.nodes[t].val) {
+            let (l, r) = self.split_by(self.nodes[t].right, go_left);
+            self.nodes[t].right = l;
+            self.recalc(t);
+            (t, r)
+        } else {
+            let (l, r) = self.split_by(self.nodes[t].left, go_left);
+            self.nodes[t].left = r;
+            self.recalc(t);
+            (l, t)
+        }
+    }
+    fn merge_node(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            self.nodes[a].right = self.merge_node(self.nodes[a].right, b);
+            self.recalc(a);
+            a
+        } else {
+            self.nodes[b].left = self.merge_node(a, self.nodes[b].left);
+            self.recalc(b);
+            b
+        }
+    }
+    fn first_by<F>(&self, t: usize, f: F) -> Option<T>
+    where
+        F: Fn(&T) -> bool,
+    {
+        //! `f`を満たす最小の要素。`f`は単調であること
+        let mut t = t;
+        let mut ret = None;
+        while t != NIL {
+            if f(&self.nodes[t].val) {
+                ret = Some(t);
+                t = self.nodes[t].left;
+            } else {
+                t = self.nodes[t].right;
+            }
+        }
+        ret.map(|t| self.nodes[t].val.clone())
+    }
+    fn rank_by<F>(&self, t: usize, f: F) -> usize
+    where
+        F: Fn(&T) -> bool,
+    {
+        //! `f`を満たす要素の個数。`f`は単調であること
+        let mut t = t;
+        let mut ret = 0;
+        while t != NIL {
+            if f(&self.nodes[t].val) {
+                ret += self.size_of(self.nodes[t].left) + 1;
+                t = self.nodes[t].right;
+            } else {
+                t = self.nodes[t].left;
+            }
+        }
+        ret
+    }
+    fn copy_subtree(&mut self, src: &Self, t: usize) -> usize {
+        //! `src`の`t`の部分木を複製し、複製した先の添字を返す
+        if t == NIL {
+            return NIL;
+        }
+        let left = self.copy_subtree(src, src.nodes[t].left);
+        let right = self.copy_subtree(src, src.nodes[t].right);
+        let i = self.new_node(src.nodes[t].val.clone());
+        let node = &mut self.nodes[i];
+        node.priority = src.nodes[t].priority;
+        node.left = left;
+        node.right = right;
+        node.size = src.nodes[t].size;
+        i
+    }
+}
+
+#[snippet("Treap")]
+impl<T> TreapArena<T> {
+    fn free_subtree(&mut self, t: usize) {
+        //! `t`の部分木のノードを解放する
+        let mut stack = vec![t];
+        while let Some(t) = stack.pop() {
+            if t == NIL {
+                continue;
+            }
+            stack.push(self.nodes[t].left);
+            stack.push(self.nodes[t].right);
+            self.free.push(t);
+        }
+    }
+}
+
+#[snippet("Treap")]
+impl<T> SortedSet<T> for Treap<T>
+where
+    T: Clone + Ord,
+{
+    fn size(&self) -> usize {
+        self.len()
+    }
+    fn add(&mut self, x: T) -> bool {
+        //! 集合のとき、既に`x`があれば`false`
+        if !self.multi && self.contains(&x) {
+            return false;
+        }
+        let mut arena = self.arena.borrow_mut();
+        let (l, r) = arena.split_by(self.root, &|y| *y < x);
+        let t = arena.new_node(x);
+        let l = arena.merge_node(l, t);
+        self.root = arena.merge_node(l, r);
+        true
+    }
+    fn remove(&mut self, x: T) -> Option<T> {
+        //! `x`を一つ削除する
+        let mut arena = self.arena.borrow_mut();
+        let (l, r) = arena.split_by(self.root, &|y| *y < x);
+        let (m, r) = arena.split_by(r, &|y| *y <= x);
+        if m == NIL {
+            self.root = arena.merge_node(l, r);
+            None
+        } else {
+            let (ml, mr) = (arena.nodes[m].left, arena.nodes[m].right);
+            let rest = arena.merge_node(ml, mr);
+            arena.free.push(m);
+            let l = arena.merge_node(l, rest);
+            self.root = arena.merge_node(l, r);
+            Some(arena.nodes[m].val.clone())
+        }
+    }
+    fn find(&self, x: T) -> Option<T> {
+        self.lower_bound(&x).filter(|y| *y == x)
+    }
+}
+
+#[snippet("Treap")]
+impl<T> Default for Treap<T>
+where
+    T: Clone + Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[snippet("Treap")]
+impl<T> Clone for Treap<T>
+where
+    T: Clone + Ord,
+{
+    fn clone(&self) -> Self {
+        //! 要素を新しい配列に複製する `O(N)`
+        let mut ret = if self.multi {
+            Self::new_multi()
+        } else {
+            Self::new()
+        };
+        ret.root = ret
+            .arena
+            .borrow_mut()
+            .copy_subtree(&self.arena.borrow(), self.root);
+        ret
+    }
+}
+
+#[snippet("Treap")]
+impl<T> Drop for Treap<T> {
+    fn drop(&mut self) {
+        // 配列を他と共有していれば、ノードを解放して再利用できるようにする
+        if std::rc::Rc::strong_count(&self.arena) > 1 {
+            self.arena.borrow_mut().free_subtree(self.root);
+        }
+    }
+}
+
+#[snippet("Treap")]
+pub struct TreapIter<'a, T> {
+    treap: &'a Treap<T>,
+    stack: Vec<usize>,
+}
+#[snippet("Treap")]
+impl<T> TreapIter<'_, T> {
+    fn push_left(&mut self, t: usize) {
+        let arena = self.treap.arena.borrow();
+        let mut t = t;
+        while t != NIL {
+            self.stack.push(t);
+            t = arena.nodes[t].left;
+        }
+    }
+}
+#[snippet("Treap")]
+impl<T> Iterator for TreapIter<'_, T>
+where
+    T: Clone,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let t = self.stack.pop()?;
+        let (right, val) = {
+            let arena = self.treap.arena.borrow();
+            (arena.nodes[t].right, arena.nodes[t].val.clone())
+        };
+        self.push_left(right);
+        Some(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::{SortedSet, Treap};
+
+    #[test]
+    fn random_multiset() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let mut treap = Treap::new_multi();
+        let mut v: Vec<i32> = vec![];
+        for _ in 0..2000 {
+            let x = rng.gen_range(0..50);
+            match rng.gen_range(0..3) {
+                0 => {
+                    assert!(treap.add(x));
+                    let i = v.partition_point(|&y| y < x);
+                    v.insert(i, x);
+                }
+                1 => {
+                    let i = v.partition_point(|&y| y < x);
+                    if v.get(i) == Some(&x) {
+                        v.remove(i);
+                        assert_eq!(treap.remove(x), Some(x));
+                    } else {
+                        assert_eq!(treap.remove(x), None);
+                    }
+                }
+                _ => {
+                    assert_eq!(treap.size(), v.len());
+                    assert_eq!(treap.rank(&x), v.partition_point(|&y| y < x));
+                    assert_eq!(treap.count(&x), v.iter().filter(|&&y| y == x).count());
+                    assert_eq!(treap.lower_bound(&x), v.iter().copied().find(|&y| x <= y));
+                    assert_eq!(treap.upper_bound(&x), v.iter().copied().find(|&y| x < y));
+                    if !v.is_empty() {
+                        let k = rng.gen_range(0..v.len());
+                        assert_eq!(treap.kth(k), Some(v[k]));
+                    }
+                }
+            }
+        }
+        assert_eq!(treap.iter().collect::<Vec<_>>(), v);
+    }
+
+    #[test]
+    fn set_split_merge() {
+        let mut treap = Treap::new();
+        for x in [5, 3, 8, 1, 9, 3, 7] {
+            treap.add(x);
+        }
+        assert_eq!(treap.len(), 6);
+        assert_eq!(treap.find(3), Some(3));
+        assert_eq!(treap.find(4), None);
+
+        let right = treap.split(&6);
+        assert_eq!(treap.iter().collect::<Vec<_>>(), vec![1, 3, 5]);
+        assert_eq!(right.iter().collect::<Vec<_>>(), vec![7, 8, 9]);
+
+        treap.add(6);
+        treap.merge(right);
+        assert_eq!(treap.iter().collect::<Vec<_>>(), vec![1, 3, 5, 6, 7, 8, 9]);
+        assert_eq!(treap.remove(8), Some(8));
+        assert_eq!(treap.kth(5), Some(9));
+    }
+
+    #[test]
+    fn split_merge_shares_nodes() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(1);
+        let mut treap = Treap::new_multi();
+        let mut v: Vec<i32> = (0..1000).map(|_| rng.gen_range(0..100)).collect();
+        for &x in &v {
+            treap.add(x);
+        }
+        v.sort();
+        for _ in 0..200 {
+            let x = rng.gen_range(0..100);
+            let mut right = treap.split(&x);
+            let i = v.partition_point(|&y| y < x);
+            assert_eq!(treap.len(), i);
+            assert_eq!(right.kth(0), v.get(i).copied());
+            // 分けた後も、それぞれ独立に操作できる
+            right.add(x);
+            treap.merge(right);
+            assert_eq!(treap.remove(x), Some(x));
+        }
+        assert_eq!(treap.iter().collect::<Vec<_>>(), v);
+        // ノードを複製していない
+        assert!(treap.arena.borrow().nodes.len() <= v.len() + 1);
+
+        // 配列を共有しないもの同士の連結と複製
+        let mut a = Treap::new();
+        let mut b = Treap::new();
+        for x in 0..5 {
+            a.add(x);
+            b.add(x + 10);
+        }
+        let c = b.clone();
+        b.add(20);
+        a.merge(c);
+        assert_eq!(
+            a.iter().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(b.len(), 6);
     }
 }
@@ -0,0 +1,378 @@
+use cargo_snippet::snippet;
+
+/// 完備辞書
+#[snippet("WaveletMatrix")]
+#[derive(Debug, Clone)]
+struct BitVector {
+    len: usize,
+    bits: Vec<u64>,
+    // acc[i] := bits[..i]に含まれる1の個数
+    acc: Vec<usize>,
+}
+#[snippet("WaveletMatrix")]
+impl BitVector {
+    fn new(v: &[bool]) -> Self {
+        let mut bits = vec![0u64; v.len() / 64 + 1];
+        for (i, &b) in v.iter().enumerate() {
+            if b {
+                bits[i >> 6] |= 1 << (i & 63);
+            }
+        }
+        let mut acc = vec![0; bits.len() + 1];
+        for i in 0..bits.len() {
+            acc[i + 1] = acc[i] + bits[i].count_ones() as usize;
+        }
+        BitVector {
+            len: v.len(),
+            bits,
+            acc,
+        }
+    }
+    fn access(&self, i: usize) -> bool {
+        self.bits[i >> 6] >> (i & 63) & 1 == 1
+    }
+    fn rank1(&self, i: usize) -> usize {
+        //! `[0, i)`にある1の個数 O(1)
+        let mask = (1u64 << (i & 63)) - 1;
+        self.acc[i >> 6] + (self.bits[i >> 6] & mask).count_ones() as usize
+    }
+    fn rank0(&self, i: usize) -> usize {
+        //! `[0, i)`にある0の個数 O(1)
+        i - self.rank1(i)
+    }
+    fn select(&self, b: bool, k: usize) -> Option<usize> {
+        //! `k`番目(0-indexed)の`b`の位置 O(log N)
+        let rank = |i: usize| if b { self.rank1(i) } else { self.rank0(i) };
+        if rank(self.len) <= k {
+            return None;
+        }
+        // rank(ok) > kとなる最小のok
+        let mut ng = 0;
+        let mut ok = self.len;
+        while ok - ng > 1 {
+            let mid = (ok + ng) / 2;
+            if rank(mid) > k {
+                ok = mid;
+            } else {
+                ng = mid;
+            }
+        }
+        Some(ok - 1)
+    }
+}
+
+/// 静的な非負整数列に対する区間の順序統計量クエリ
+///
+/// 各クエリ`O(log V)`
+#[snippet("WaveletMatrix")]
+#[derive(Debug, Clone)]
+pub struct WaveletMatrix {
+    len: usize,
+    bit_len: usize,
+    // bv[d] := 上から d 番目のbit
+    bv: Vec<BitVector>,
+    zeros: Vec<usize>,
+    // sum[d] := d 段目で安定ソートした後の列の累積和
+    sum: Option<Vec<Vec<u64>>>,
+}
+#[snippet("WaveletMatrix")]
+impl WaveletMatrix {
+    pub fn new(v: &[u64]) -> Self {
+        //! `O(N log V)`
+        Self::build(v, false)
+    }
+    pub fn with_sum(v: &[u64]) -> Self {
+        //! `sum_less`用に各段の累積和も持つ
+        //!
+        //! `O(N log V)`
+        Self::build(v, true)
+    }
+    fn build(v: &[u64], with_sum: bool) -> Self {
+        let max = v.iter().max().copied().unwrap_or(0);
+        let bit_len = (64 - max.leading_zeros() as usize).max(1);
+        let mut cur = v.to_vec();
+        let mut bv = Vec::with_capacity(bit_len);
+        let mut zeros = Vec::with_capacity(bit_len);
+        let mut sum = Vec::with_capacity(bit_len);
+        for d in 0..bit_len {
+            let b = bit_len - 1 - d;
+            let bits = cur.iter().map(|&x| x >> b & 1 == 1).collect::<Vec<bool>>();
+            let (mut zero, one): (Vec<u64>, Vec<u64>) = cur.iter().partition(|&&x| x >> b & 1 == 0);
+            bv.push(BitVector::new(&bits));
+            zeros.push(zero.len());
+            zero.extend(one);
+            cur = zero;
+            if with_sum {
+                let mut acc = vec![0; cur.len() + 1];
+                for i in 0..cur.len() {
+                    acc[i + 1] = acc[i] + cur[i];
+                }
+                sum.push(acc);
+            }
+        }
+        WaveletMatrix {
+            len: v.len(),
+            bit_len,
+            bv,
+            zeros,
+            sum: if with_sum { Some(sum) } else { None },
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    fn range_to_tuple<R>(range: R, r_max: usize) -> (usize, usize)
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        use std::ops::Bound;
+        let l = match range.start_bound() {
+            Bound::Included(l) => *l,
+            Bound::Excluded(l) => l + 1,
+            Bound::Unbounded => 0,
+        };
+        let r = match range.end_bound() {
+            Bound::Included(r) => r + 1,
+            Bound::Excluded(r) => *r,
+            Bound::Unbounded => r_max,
+        };
+        (l, r)
+    }
+    fn is_over(&self, x: u64) -> bool {
+        //! `x`がどの値よりも大きいbit長を持つか
+        x.checked_shr(self.bit_len as u32).unwrap_or(0) > 0
+    }
+    // d段目で[l, r)をbに従って下の段に移す
+    fn down(&self, d: usize, l: usize, r: usize, b: bool) -> (usize, usize) {
+        if b {
+            (
+                self.zeros[d] + self.bv[d].rank1(l),
+                self.zeros[d] + self.bv[d].rank1(r),
+            )
+        } else {
+            (self.bv[d].rank0(l), self.bv[d].rank0(r))
+        }
+    }
+    pub fn access(&self, i: usize) -> u64 {
+        //! `i`番目の値
+        assert!(i < self.len);
+        let mut i = i;
+        let mut ret = 0;
+        for d in 0..self.bit_len {
+            let b = self.bv[d].access(i);
+            if b {
+                ret |= 1 << (self.bit_len - 1 - d);
+            }
+            i = self.down(d, i, i, b).0;
+        }
+        ret
+    }
+    pub fn rank(&self, x: u64, r: usize) -> usize {
+        //! `[0, r)`にある`x`の個数
+        assert!(r <= self.len);
+        if self.is_over(x) {
+            return 0;
+        }
+        let (mut l, mut r) = (0, r);
+        for d in 0..self.bit_len {
+            (l, r) = self.down(d, l, r, x >> (self.bit_len - 1 - d) & 1 == 1);
+        }
+        r - l
+    }
+    pub fn select(&self, x: u64, k: usize) -> Option<usize> {
+        //! `k`番目(0-indexed)の`x`の位置
+        if self.is_over(x) {
+            return None;
+        }
+        let (mut l, mut r) = (0, self.len);
+        for d in 0..self.bit_len {
+            (l, r) = self.down(d, l, r, x >> (self.bit_len - 1 - d) & 1 == 1);
+        }
+        if r - l <= k {
+            return None;
+        }
+        let mut pos = l + k;
+        for d in (0..self.bit_len).rev() {
+            pos = if x >> (self.bit_len - 1 - d) & 1 == 1 {
+                self.bv[d].select(true, pos - self.zeros[d])?
+            } else {
+                self.bv[d].select(false, pos)?
+            };
+        }
+        Some(pos)
+    }
+    pub fn kth_smallest<R>(&self, range: R, k: usize) -> u64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間で`k`番目(0-indexed)に小さい値
+        let (mut l, mut r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        assert!(k < r - l);
+        let mut k = k;
+        let mut ret = 0;
+        for d in 0..self.bit_len {
+            let cnt0 = self.bv[d].rank0(r) - self.bv[d].rank0(l);
+            let b = cnt0 <= k;
+            if b {
+                k -= cnt0;
+                ret |= 1 << (self.bit_len - 1 - d);
+            }
+            (l, r) = self.down(d, l, r, b);
+        }
+        ret
+    }
+    pub fn kth_largest<R>(&self, range: R, k: usize) -> u64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間で`k`番目(0-indexed)に大きい値
+        let (l, r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        assert!(k < r - l);
+        self.kth_smallest(l..r, r - l - 1 - k)
+    }
+    fn count_less(&self, l: usize, r: usize, upper: u64) -> usize {
+        if self.is_over(upper) {
+            return r - l;
+        }
+        let (mut l, mut r) = (l, r);
+        let mut ret = 0;
+        for d in 0..self.bit_len {
+            let b = upper >> (self.bit_len - 1 - d) & 1 == 1;
+            if b {
+                ret += self.bv[d].rank0(r) - self.bv[d].rank0(l);
+            }
+            (l, r) = self.down(d, l, r, b);
+        }
+        ret
+    }
+    pub fn range_freq<R>(&self, range: R, lower: u64, upper: u64) -> usize
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`lower <= x < upper`を満たす`x`の個数
+        let (l, r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        if upper <= lower {
+            return 0;
+        }
+        self.count_less(l, r, upper) - self.count_less(l, r, lower)
+    }
+    pub fn prev_value<R>(&self, range: R, upper: u64) -> Option<u64>
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`upper`未満の最大値
+        let (l, r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        let cnt = self.count_less(l, r, upper);
+        if cnt == 0 {
+            None
+        } else {
+            Some(self.kth_smallest(l..r, cnt - 1))
+        }
+    }
+    pub fn next_value<R>(&self, range: R, lower: u64) -> Option<u64>
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`lower`以上の最小値
+        let (l, r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        let cnt = self.count_less(l, r, lower);
+        if cnt == r - l {
+            None
+        } else {
+            Some(self.kth_smallest(l..r, cnt))
+        }
+    }
+    pub fn sum_less<R>(&self, range: R, upper: u64) -> u64
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        //! 区間にある`upper`未満の値の総和
+        //!
+        //! `with_sum`で構築している必要がある
+        let sum = self
+            .sum
+            .as_ref()
+            .expect("WaveletMatrix is not built with_sum");
+        let (mut l, mut r) = Self::range_to_tuple(range, self.len);
+        assert!(l <= r && r <= self.len);
+        if self.is_over(upper) {
+            // 1段目で0側と1側に分かれた全体の和
+            let (l0, r0) = self.down(0, l, r, false);
+            let (l1, r1) = self.down(0, l, r, true);
+            return sum[0][r0] - sum[0][l0] + sum[0][r1] - sum[0][l1];
+        }
+        let mut ret = 0;
+        for (d, acc) in sum.iter().enumerate() {
+            let b = upper >> (self.bit_len - 1 - d) & 1 == 1;
+            if b {
+                let (l0, r0) = self.down(d, l, r, false);
+                ret += acc[r0] - acc[l0];
+            }
+            (l, r) = self.down(d, l, r, b);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::WaveletMatrix;
+
+    #[test]
+    fn random() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..20 {
+            let n = rng.gen_range(1..=30);
+            let v = (0..n).map(|_| rng.gen_range(0..20)).collect::<Vec<u64>>();
+            let wm = WaveletMatrix::with_sum(&v);
+            for (i, &x) in v.iter().enumerate() {
+                assert_eq!(wm.access(i), x);
+            }
+            for x in 0..25 {
+                for r in 0..=n {
+                    assert_eq!(wm.rank(x, r), v[..r].iter().filter(|&&y| y == x).count());
+                }
+                let pos = (0..n).filter(|&i| v[i] == x).collect::<Vec<usize>>();
+                for k in 0..=pos.len() {
+                    assert_eq!(wm.select(x, k), pos.get(k).copied());
+                }
+            }
+            for _ in 0..100 {
+                let l = rng.gen_range(0..n);
+                let r = rng.gen_range(l + 1..=n);
+                let mut sorted = v[l..r].to_vec();
+                sorted.sort();
+                let k = rng.gen_range(0..r - l);
+                assert_eq!(wm.kth_smallest(l..r, k), sorted[k]);
+                assert_eq!(wm.kth_largest(l..r, k), sorted[r - l - 1 - k]);
+
+                let lower = rng.gen_range(0..25);
+                let upper = rng.gen_range(0..40);
+                let cnt = sorted.iter().filter(|&&x| lower <= x && x < upper).count();
+                assert_eq!(wm.range_freq(l..r, lower, upper), cnt);
+                assert_eq!(
+                    wm.prev_value(l..r, upper),
+                    sorted.iter().filter(|&&x| x < upper).max().copied()
+                );
+                assert_eq!(
+                    wm.next_value(l..r, lower),
+                    sorted.iter().filter(|&&x| lower <= x).min().copied()
+                );
+                assert_eq!(
+                    wm.sum_less(l..r, upper),
+                    sorted.iter().filter(|&&x| x < upper).sum::<u64>()
+                );
+            }
+        }
+    }
+}
@@ -1,16 +1,28 @@
+pub mod bipartite;
+pub mod csr;
+pub mod general_matching;
+pub mod grid;
+pub mod lowlink;
+pub mod maxflow;
+pub mod mincostflow;
 pub mod scc;
 pub mod unionfind;
 
+use crate::{algebra::Additive, traits::Monoid};
 use cargo_snippet::snippet;
+use csr::AdjacencyList;
 use unionfind::UnionFind;
 
-pub fn cycle_detection(
-    g: &[Vec<usize>],
+pub fn cycle_detection<G>(
+    g: &G,
     v: usize,
     seen: &mut Vec<bool>,
     finished: &mut Vec<bool>,
     history: &mut Vec<usize>,
-) -> Option<usize> {
+) -> Option<usize>
+where
+    G: AdjacencyList + ?Sized,
+{
     //! サイクルがあれば、その起点の`Some(usize)`を返す
     //!
     //! 有向グラフのみ
@@ -18,7 +30,7 @@ pub fn cycle_detection(
     //! `O(|V| + |E|)`
     seen[v] = true;
     history.push(v);
-    for &nv in &g[v] {
+    for (nv, _) in g.neighbors(v) {
         if v == nv {
             continue;
         }
@@ -57,7 +69,10 @@ pub fn cycle_reconstruct(pos: usize, history: &mut Vec<usize>) -> Vec<usize> {
 }
 
 // #[snippet]
-pub fn euler_tour(v: usize, g: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
+pub fn euler_tour<G>(v: usize, g: &G) -> (Vec<usize>, Vec<usize>)
+where
+    G: AdjacencyList + ?Sized,
+{
     //! 行きがけ順、帰りがけ順を半開区間にして出力。
     //! 部分木に対するクエリを区間のクエリに言い換える。
     //!
@@ -75,7 +90,7 @@ pub fn euler_tour(v: usize, g: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
             1 => {
                 seen[v] = true;
                 v_in[v] = cnt;
-                for &nv in g[v].iter().rev() {
+                for (nv, _) in g.neighbors(v).rev() {
                     if seen[nv] {
                         continue;
                     }
@@ -94,137 +109,593 @@ pub fn euler_tour(v: usize, g: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
 }
 
 #[snippet]
-pub fn dfs(v: usize, p: usize, g: &[Vec<usize>]) {
-    for nv in &g[v] {
-        if p == *nv {
+#[snippet(include = "AdjacencyList")]
+pub fn dfs<G>(v: usize, p: usize, g: &G)
+where
+    G: AdjacencyList + ?Sized,
+{
+    for (nv, _) in g.neighbors(v) {
+        if p == nv {
             continue;
         }
-        dfs(*nv, p, g);
+        dfs(nv, v, g);
+    }
+}
+
+/// 単一始点最短路の結果
+#[snippet("ShortestPath")]
+#[derive(Debug, Clone)]
+pub struct ShortestPath<T> {
+    /// 到達できなければ`None`
+    pub dist: Vec<Option<T>>,
+    /// 最短路木での親
+    pub prev: Vec<Option<usize>>,
+}
+#[snippet("ShortestPath")]
+impl<T> ShortestPath<T> {
+    fn new(n: usize) -> Self {
+        ShortestPath {
+            dist: (0..n).map(|_| None).collect(),
+            prev: vec![None; n],
+        }
+    }
+    pub fn path_to(&self, v: usize) -> Option<Vec<usize>> {
+        //! 始点から`v`への最短路の頂点を順に返す
+        self.dist[v].as_ref()?;
+        let mut path = vec![v];
+        let mut v = v;
+        while let Some(p) = self.prev[v] {
+            path.push(p);
+            v = p;
+        }
+        path.reverse();
+        Some(path)
     }
 }
 
 #[snippet]
-pub fn bfs(st: usize, g: &[Vec<usize>]) -> Vec<isize> {
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "ShortestPath")]
+pub fn bfs<G>(st: usize, g: &G) -> ShortestPath<usize>
+where
+    G: AdjacencyList + ?Sized,
+{
     let n = g.len();
-    let mut dist: Vec<isize> = vec![-1; n];
+    let mut sp = ShortestPath::new(n);
     let mut q = std::collections::VecDeque::new();
-    q.push_back(st);
-    dist[st] = 0;
-    while let Some(v) = q.pop_front() {
-        for nv in &g[v] {
-            if dist[*nv] != -1 {
+    q.push_back((st, 0));
+    sp.dist[st] = Some(0);
+    while let Some((v, d)) = q.pop_front() {
+        for (nv, _) in g.neighbors(v) {
+            if sp.dist[nv].is_some() {
+                continue;
+            }
+            sp.dist[nv] = Some(d + 1);
+            sp.prev[nv] = Some(v);
+            q.push_back((nv, d + 1));
+        }
+    }
+    sp
+}
+
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "ShortestPath")]
+pub fn bfs01<G>(starts: &[usize], g: &G) -> ShortestPath<usize>
+where
+    G: AdjacencyList<W = usize> + ?Sized,
+{
+    //! 重みが0か1のグラフの、`starts`からの最短路
+    //!
+    //! `O(|V| + |E|)`
+    let n = g.len();
+    let mut sp = ShortestPath::new(n);
+    let mut q = std::collections::VecDeque::new();
+    for &st in starts {
+        sp.dist[st] = Some(0);
+        q.push_back((st, 0));
+    }
+    while let Some((v, d)) = q.pop_front() {
+        if sp.dist[v] != Some(d) {
+            continue;
+        }
+        for (nv, &c) in g.neighbors(v) {
+            assert!(c <= 1);
+            let nd = d + c;
+            if sp.dist[nv].is_none_or(|x| x > nd) {
+                sp.dist[nv] = Some(nd);
+                sp.prev[nv] = Some(v);
+                if c == 0 {
+                    q.push_front((nv, nd));
+                } else {
+                    q.push_back((nv, nd));
+                }
+            }
+        }
+    }
+    sp
+}
+
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "ShortestPath")]
+pub fn dial<G>(starts: &[usize], g: &G, max_w: usize) -> ShortestPath<usize>
+where
+    G: AdjacencyList<W = usize> + ?Sized,
+{
+    //! 重みが`max_w`以下の整数のグラフの、`starts`からの最短路
+    //!
+    //! 距離で分けたバケツを、長さ`max_w + 1`の環状に使い回す
+    //!
+    //! `O(|E| + |V| max_w)`
+    let n = g.len();
+    let mut sp = ShortestPath::new(n);
+    let mut buckets = vec![vec![]; max_w + 1];
+    for &st in starts {
+        sp.dist[st] = Some(0);
+        buckets[0].push(st);
+    }
+    let mut rest = starts.len();
+    let mut d = 0;
+    while rest > 0 {
+        let i = d % (max_w + 1);
+        while let Some(v) = buckets[i].pop() {
+            rest -= 1;
+            if sp.dist[v] != Some(d) {
                 continue;
             }
-            dist[*nv] = dist[v] + 1;
-            q.push_back(*nv);
+            for (nv, &c) in g.neighbors(v) {
+                assert!(c <= max_w);
+                let nd = d + c;
+                if sp.dist[nv].is_none_or(|x| x > nd) {
+                    sp.dist[nv] = Some(nd);
+                    sp.prev[nv] = Some(v);
+                    buckets[nd % (max_w + 1)].push(nv);
+                    rest += 1;
+                }
+            }
         }
+        d += 1;
+    }
+    sp
+}
+
+/// 全点対間最短路の結果
+#[snippet("WarshallFloyd")]
+#[derive(Debug, Clone)]
+pub struct WarshallFloyd {
+    /// 到達できなければ`None`
+    pub dist: Vec<Vec<Option<isize>>>,
+    /// `next[u][v]`は`u`から`v`への最短路で、`u`の次の頂点
+    pub next: Vec<Vec<Option<usize>>>,
+}
+#[snippet("WarshallFloyd")]
+impl WarshallFloyd {
+    pub fn has_negative_cycle(&self) -> bool {
+        (0..self.dist.len()).any(|v| self.dist[v][v].is_some_and(|d| d < 0))
+    }
+    pub fn path(&self, u: usize, v: usize) -> Option<Vec<usize>> {
+        //! `u`から`v`への最短路の頂点を順に返す
+        //!
+        //! 負閉路がないこと
+        self.dist[u][v]?;
+        let mut path = vec![u];
+        let mut x = u;
+        while x != v {
+            x = self.next[x][v].unwrap();
+            path.push(x);
+        }
+        Some(path)
     }
-    dist
 }
 
 #[snippet]
-pub fn warshall_froyd(g: &[Vec<(isize, usize)>]) {
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "WarshallFloyd")]
+pub fn warshall_froyd<G>(g: &G) -> WarshallFloyd
+where
+    G: AdjacencyList<W = isize> + ?Sized,
+{
     //! 全始点最短路
     //!
-    //! `(cost, nv)`のの隣接リスト
+    //! 負閉路があるかは`has_negative_cycle`で確かめる
+    //!
+    //! `isize`に収まらない長さの経路は使わない
     //!
     //! `O(|V|^3)`
-
-    const INF: isize = isize::MAX;
     let n = g.len();
-    let mut dist = vec![vec![INF; g.len()]; g.len()];
+    let mut dist = vec![vec![None; n]; n];
+    let mut next = vec![vec![None; n]; n];
     for v in 0..n {
-        for &(cost, nv) in &g[v] {
-            dist[v][nv] = isize::min(dist[v][nv], cost);
+        dist[v][v] = Some(0);
+        next[v][v] = Some(v);
+        for (nv, &cost) in g.neighbors(v) {
+            if dist[v][nv].is_none_or(|d| d > cost) {
+                dist[v][nv] = Some(cost);
+                next[v][nv] = Some(nv);
+            }
         }
     }
     for k in 0..n {
         for i in 0..n {
+            let Some(dik) = dist[i][k] else {
+                continue;
+            };
             for j in 0..n {
-                dist[i][j] = dist[i][j].min(dist[i][k] + dist[k][j])
+                let Some(dkj) = dist[k][j] else {
+                    continue;
+                };
+                let Some(d) = dik.checked_add(dkj) else {
+                    continue;
+                };
+                if dist[i][j].is_none_or(|x| x > d) {
+                    dist[i][j] = Some(d);
+                    next[i][j] = next[i][k];
+                }
             }
         }
     }
+    WarshallFloyd { dist, next }
 }
 
 #[snippet]
-pub fn dijkstra(st: usize, g: &[Vec<(usize, usize)>]) -> Vec<usize> {
+#[snippet(include = "dijkstra_by")]
+#[snippet(include = "Additive")]
+pub fn dijkstra<G>(st: usize, g: &G) -> ShortestPath<usize>
+where
+    G: AdjacencyList<W = usize> + ?Sized,
+{
     //! 単一始点最短路を求める
     //!
-    //! `(cost, nv)`の隣接リストを要求
+    //! `O(|E|log|V|)`
+    dijkstra_by::<Additive<usize>, _>(&[st], g, None)
+}
+
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "Monoid")]
+#[snippet(include = "ShortestPath")]
+pub fn dijkstra_by<M, G>(starts: &[usize], g: &G, target: Option<usize>) -> ShortestPath<M::S>
+where
+    M: Monoid,
+    M::S: Ord,
+    G: AdjacencyList<W = M::S> + ?Sized,
+{
+    //! 重みを`M`で足していく、`starts`からの最短路を求める
+    //!
+    //! 重みは`M::e()`以上で、足しても小さくならないこと
+    //!
+    //! `target`を確定した時点で打ち切る。このとき、未確定の頂点の`dist`は暫定値
     //!
     //! `O(|E|log|V|)`
 
     use std::cmp::Reverse;
 
     let n = g.len();
-    let inf: usize = 1 << 60;
     let mut kakutei = vec![false; n];
-    let mut dist = vec![inf; n];
+    let mut sp = ShortestPath::new(n);
     let mut pq = std::collections::BinaryHeap::new();
 
-    dist[st] = 0;
-    pq.push(Reverse((dist[st], st)));
+    for &st in starts {
+        sp.dist[st] = Some(M::e());
+        pq.push(Reverse((M::e(), st)));
+    }
 
-    while let Some(Reverse((_, v))) = pq.pop() {
+    while let Some(Reverse((d, v))) = pq.pop() {
         if kakutei[v] {
             continue;
         }
 
         kakutei[v] = true;
-        for (ncos, nv) in &g[v] {
-            if dist[*nv] > dist[v] + ncos {
-                dist[*nv] = dist[v] + ncos;
-                pq.push(Reverse((dist[*nv], *nv)))
+        if Some(v) == target {
+            break;
+        }
+        for (nv, ncos) in g.neighbors(v) {
+            let nd = M::op(&d, ncos);
+            if sp.dist[nv].as_ref().is_none_or(|x| *x > nd) {
+                sp.dist[nv] = Some(nd.clone());
+                sp.prev[nv] = Some(v);
+                pq.push(Reverse((nd, nv)))
             }
         }
     }
-    dist
+    sp
 }
 
 #[snippet]
-pub fn bellman_ford(st: usize, g: &[Vec<(isize, usize)>]) -> Vec<isize> {
-    //! `st`からの単一始点最短路を返す
+#[snippet(include = "Monoid")]
+#[snippet(include = "ShortestPath")]
+pub fn dijkstra_dense<M>(starts: &[usize], cost: &[Vec<Option<M::S>>]) -> ShortestPath<M::S>
+where
+    M: Monoid,
+    M::S: Ord,
+{
+    //! 隣接行列`cost`で表される密なグラフの、`starts`からの最短路を求める
     //!
-    //! `(cost, nv)`の隣接リストを要求
+    //! `cost[v][nv]`は辺がなければ`None`
     //!
-    //! 不閉路になるものは`-inf`にして返す
+    //! `O(|V|^2)`
+    let n = cost.len();
+    let mut kakutei = vec![false; n];
+    let mut sp = ShortestPath::new(n);
+    for &st in starts {
+        sp.dist[st] = Some(M::e());
+    }
+    loop {
+        let mut v = None;
+        for (i, d) in sp.dist.iter().enumerate() {
+            if kakutei[i] || d.is_none() {
+                continue;
+            }
+            if v.is_none_or(|v: usize| sp.dist[v] > *d) {
+                v = Some(i);
+            }
+        }
+        let Some(v) = v else {
+            break;
+        };
+        kakutei[v] = true;
+        let d = sp.dist[v].clone().unwrap();
+        for (nv, c) in cost[v].iter().enumerate() {
+            let Some(c) = c else {
+                continue;
+            };
+            let nd = M::op(&d, c);
+            if sp.dist[nv].as_ref().is_none_or(|x| *x > nd) {
+                sp.dist[nv] = Some(nd);
+                sp.prev[nv] = Some(v);
+            }
+        }
+    }
+    sp
+}
+
+#[snippet]
+#[snippet(include = "dijkstra_by")]
+pub fn count_shortest_paths<M, G>(starts: &[usize], g: &G, p: u64) -> (ShortestPath<M::S>, Vec<u64>)
+where
+    M: Monoid,
+    M::S: Ord,
+    G: AdjacencyList<W = M::S> + ?Sized,
+{
+    //! `starts`からの最短路と、各頂点への最短路の数を`p`で割った余りを返す
     //!
-    //! `O(|V| |E|)`
+    //! 重みは`M::e()`より真に大きいこと
+    //!
+    //! `O(|E|log|V|)`
+    let sp = dijkstra_by::<M, G>(starts, g, None);
     let n = g.len();
-    const INF: isize = isize::MAX;
-    const NEG_INF: isize = isize::MIN;
+    let mut order: Vec<usize> = (0..n).filter(|&v| sp.dist[v].is_some()).collect();
+    order.sort_by(|&a, &b| sp.dist[a].cmp(&sp.dist[b]));
+    let mut cnt = vec![0; n];
+    for &st in starts {
+        cnt[st] = 1 % p;
+    }
+    for v in order {
+        let d = sp.dist[v].as_ref().unwrap();
+        for (nv, c) in g.neighbors(v) {
+            if sp.dist[nv].as_ref() == Some(&M::op(d, c)) {
+                cnt[nv] = (cnt[nv] + cnt[v]) % p;
+            }
+        }
+    }
+    (sp, cnt)
+}
 
-    let mut dist = vec![INF; n];
-    dist[st] = 0;
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "ShortestPath")]
+pub fn bellman_ford<G>(st: usize, g: &G) -> (ShortestPath<isize>, Vec<bool>)
+where
+    G: AdjacencyList<W = isize> + ?Sized,
+{
+    //! `st`からの単一始点最短路と、負閉路を通っていくらでも短くできる頂点を返す
+    //!
+    //! いくらでも短くできる頂点の`dist`は`None`
+    //!
+    //! `isize`に収まらない長さの経路は使わない
+    //!
+    //! `O(|V| |E|)`
+    let n = g.len();
+    let mut sp = ShortestPath::<isize>::new(n);
+    sp.dist[st] = Some(0);
 
-    for _ in 0..n - 1 {
+    for _ in 1..n {
         for v in 0..n {
-            if dist[v] == INF {
+            let Some(d) = sp.dist[v] else {
                 continue;
-            }
-            for (c, nv) in &g[v] {
-                if dist[*nv] > dist[v] + c {
-                    dist[*nv] = dist[v] + c
+            };
+            for (nv, &c) in g.neighbors(v) {
+                let Some(nd) = d.checked_add(c) else {
+                    continue;
+                };
+                if sp.dist[nv].is_none_or(|x| x > nd) {
+                    sp.dist[nv] = Some(nd);
+                    sp.prev[nv] = Some(v);
                 }
             }
         }
     }
     // 負閉路検出
-    // 負閉路となるものは-infに変更
+    // まだ緩和できる頂点と、そこから到達できる頂点は-inf
+    let mut neg_inf = vec![false; n];
     for _ in 0..n {
         for v in 0..n {
-            for (c, nv) in &g[v] {
-                if dist[*nv] == INF {
+            let Some(d) = sp.dist[v] else {
+                continue;
+            };
+            for (nv, &c) in g.neighbors(v) {
+                let relaxable = d
+                    .checked_add(c)
+                    .is_some_and(|nd| sp.dist[nv].is_none_or(|x| x > nd));
+                if neg_inf[v] || relaxable {
+                    neg_inf[nv] = true;
+                }
+            }
+        }
+    }
+    for (v, &b) in neg_inf.iter().enumerate() {
+        if b {
+            sp.dist[v] = None;
+            sp.prev[v] = None;
+        }
+    }
+    (sp, neg_inf)
+}
+
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+pub fn find_negative_cycle<G>(g: &G) -> Option<Vec<usize>>
+where
+    G: AdjacencyList<W = isize> + ?Sized,
+{
+    //! 負閉路があれば、その頂点を辿る順に返す
+    //!
+    //! 始点によらず、グラフ全体から探す
+    //!
+    //! `O(|V| |E|)`
+    let n = g.len();
+    // 全頂点を距離0の始点とする
+    let mut dist = vec![0isize; n];
+    let mut prev = vec![None; n];
+    let mut last = None;
+    for _ in 0..n {
+        last = None;
+        for v in 0..n {
+            for (nv, &c) in g.neighbors(v) {
+                let Some(nd) = dist[v].checked_add(c) else {
                     continue;
+                };
+                if dist[nv] > nd {
+                    dist[nv] = nd;
+                    prev[nv] = Some(v);
+                    last = Some(nv);
+                }
+            }
+        }
+        last?;
+    }
+    // n回目にも緩和できた頂点から、n回戻れば負閉路の上にいる
+    let mut v = last?;
+    for _ in 0..n {
+        v = prev[v].unwrap();
+    }
+    let mut cycle = vec![v];
+    let mut x = prev[v].unwrap();
+    while x != v {
+        cycle.push(x);
+        x = prev[x].unwrap();
+    }
+    cycle.reverse();
+    Some(cycle)
+}
+
+#[snippet]
+#[snippet(include = "AdjacencyList")]
+#[snippet(include = "ShortestPath")]
+pub fn spfa<G>(st: usize, g: &G) -> Option<ShortestPath<isize>>
+where
+    G: AdjacencyList<W = isize> + ?Sized,
+{
+    //! キューで更新のあった頂点だけを緩和するベルマンフォード
+    //!
+    //! `st`から負閉路に到達できれば`None`
+    //!
+    //! `isize`に収まらない長さの経路は使わない
+    //!
+    //! 最悪`O(|V| |E|)`
+    let n = g.len();
+    let mut sp = ShortestPath::<isize>::new(n);
+    let mut in_queue = vec![false; n];
+    // 最短路の辺の数
+    let mut len = vec![0; n];
+    let mut q = std::collections::VecDeque::new();
+    sp.dist[st] = Some(0);
+    in_queue[st] = true;
+    q.push_back(st);
+    while let Some(v) = q.pop_front() {
+        in_queue[v] = false;
+        let d = sp.dist[v].unwrap();
+        for (nv, &c) in g.neighbors(v) {
+            let Some(nd) = d.checked_add(c) else {
+                continue;
+            };
+            if sp.dist[nv].is_none_or(|x| x > nd) {
+                sp.dist[nv] = Some(nd);
+                sp.prev[nv] = Some(v);
+                len[nv] = len[v] + 1;
+                if len[nv] >= n {
+                    return None;
                 }
-                if dist[*nv] > dist[v] + c {
-                    dist[*nv] = NEG_INF
+                if !in_queue[nv] {
+                    in_queue[nv] = true;
+                    q.push_back(nv);
                 }
             }
         }
     }
-    dist
+    Some(sp)
+}
+
+#[snippet]
+#[snippet(include = "dijkstra")]
+pub fn johnson<G>(g: &G) -> Option<Vec<Vec<Option<isize>>>>
+where
+    G: AdjacencyList<W = isize> + ?Sized,
+{
+    //! 負辺を含む疎なグラフの全点対間最短路
+    //!
+    //! 負閉路があれば`None`
+    //!
+    //! `isize`に収まらない長さの経路は使わない
+    //!
+    //! `O(|V| |E| log|V|)`
+    let n = g.len();
+    // 全頂点を距離0の始点としたベルマンフォードでポテンシャルを求める
+    let mut h = vec![0isize; n];
+    for round in 0..=n {
+        let mut updated = false;
+        for v in 0..n {
+            for (nv, &c) in g.neighbors(v) {
+                let Some(nh) = h[v].checked_add(c) else {
+                    continue;
+                };
+                if h[nv] > nh {
+                    h[nv] = nh;
+                    updated = true;
+                }
+            }
+        }
+        if !updated {
+            break;
+        }
+        if round == n {
+            return None;
+        }
+    }
+    // 重みを非負に付け替える
+    // hは0以下なので、c + h[v]は上にはあふれず、h[nv]を引いた値はusizeに収まる
+    let rg: Vec<Vec<(usize, usize)>> = (0..n)
+        .map(|v| {
+            g.neighbors(v)
+                .filter_map(|(nv, &c)| {
+                    let x = c.checked_add(h[v])?;
+                    (x >= h[nv]).then(|| (x.abs_diff(h[nv]), nv))
+                })
+                .collect()
+        })
+        .collect();
+    let dist = (0..n)
+        .map(|s| {
+            dijkstra(s, &rg)
+                .dist
+                .iter()
+                .enumerate()
+                .map(|(t, &d)| isize::try_from(d? as i128 - h[s] as i128 + h[t] as i128).ok())
+                .collect()
+        })
+        .collect();
+    Some(dist)
 }
 
 #[snippet]
@@ -252,41 +723,44 @@ pub fn kruskal(mut e: Vec<(isize, usize, usize)>, n: usize) -> (Vec<(isize, usiz
 }
 
 // #[snippet]
-pub fn isbiparrite(g: &[Vec<usize>]) -> bool {
-    //! 二部グラフか否か
+pub fn isbiparrite<G>(g: &G) -> Option<Vec<usize>>
+where
+    G: AdjacencyList + ?Sized,
+{
+    //! 二部グラフなら、各頂点を0か1で塗り分けて返す
+    //!
+    //! 無向グラフのみ
     //!
     //! `O(|V| + |E|)`
     let n = g.len();
-    let mut iro = vec![-1; n];
-    let mut ok = true;
+    let mut iro = vec![usize::MAX; n];
 
     for i in 0..n {
-        if !ok {
-            break;
-        }
-        if iro[i] != -1 {
+        if iro[i] != usize::MAX {
             continue;
         }
         let mut q = std::collections::VecDeque::new();
-        q.push_back((i, 0));
+        q.push_back(i);
         iro[i] = 0;
 
-        while let Some((v, c)) = q.pop_front() {
-            for &nv in &g[v] {
-                if iro[nv] == -1 {
-                    iro[nv] = c ^ 1;
-                    q.push_back((nv, iro[nv]));
-                } else if c == iro[nv] {
-                    ok = false;
-                    return ok;
+        while let Some(v) = q.pop_front() {
+            for (nv, _) in g.neighbors(v) {
+                if iro[nv] == usize::MAX {
+                    iro[nv] = iro[v] ^ 1;
+                    q.push_back(nv);
+                } else if iro[v] == iro[nv] {
+                    return None;
                 }
             }
         }
     }
-    ok
+    Some(iro)
 }
 
-pub fn topological_sort(g: &[Vec<usize>], indeg: &mut [usize]) -> Vec<usize> {
+pub fn topological_sort<G>(g: &G, indeg: &mut [usize]) -> Vec<usize>
+where
+    G: AdjacencyList + ?Sized,
+{
     let mut ret = vec![];
     let mut q = std::collections::VecDeque::new();
     for i in 0..indeg.len() {
@@ -295,10 +769,10 @@ pub fn topological_sort(g: &[Vec<usize>], indeg: &mut [usize]) -> Vec<usize> {
         }
     }
     while let Some(v) = q.pop_front() {
-        for nv in &g[v] {
-            indeg[*nv] -= 1;
-            if indeg[*nv] == 0 {
-                q.push_back(*nv);
+        for (nv, _) in g.neighbors(v) {
+            indeg[nv] -= 1;
+            if indeg[nv] == 0 {
+                q.push_back(nv);
             }
         }
         ret.push(v);
@@ -308,8 +782,272 @@ pub fn topological_sort(g: &[Vec<usize>], indeg: &mut [usize]) -> Vec<usize> {
 
 #[cfg(test)]
 mod test {
-    use crate::graph::euler_tour;
+    use crate::algebra::Additive;
+    use crate::graph::{
+        bellman_ford, bfs, bfs01, count_shortest_paths, csr::Graph, dial, dijkstra, dijkstra_by,
+        dijkstra_dense, euler_tour, find_negative_cycle, isbiparrite, johnson, spfa,
+        warshall_froyd,
+    };
+    use crate::traits::Monoid;
     use itertools::Itertools;
+    use rand::Rng;
+
+    #[test]
+    fn csr_graph() {
+        let edges = vec![(0, 1, 4), (1, 2, 1), (0, 2, 7), (2, 3, 2)];
+        let g = Graph::undirected(5, edges.clone());
+        let mut adj = vec![vec![]; 5];
+        for &(u, v, c) in &edges {
+            adj[u].push((c, v));
+            adj[v].push((c, u));
+        }
+        assert_eq!(dijkstra(0, &g).dist, dijkstra(0, &adj).dist);
+        assert_eq!(
+            dijkstra(0, &g).dist,
+            [Some(0), Some(4), Some(5), Some(7), None]
+        );
+        assert_eq!(bfs(0, &g).dist, [Some(0), Some(1), Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn shortest_path() {
+        let edges = vec![(0, 1, 4), (1, 2, 1), (0, 2, 7), (2, 3, 2), (4, 3, 1)];
+        let g = Graph::directed(5, edges.clone());
+        let sp = dijkstra(0, &g);
+        assert_eq!(sp.path_to(3), Some(vec![0, 1, 2, 3]));
+        assert_eq!(sp.path_to(0), Some(vec![0]));
+        assert_eq!(sp.path_to(4), None);
+        assert_eq!(bfs(0, &g).path_to(3), Some(vec![0, 2, 3]));
+
+        let g = Graph::directed(
+            5,
+            edges.iter().map(|&(u, v, c)| (u, v, c as isize)).collect(),
+        );
+        let (sp, neg_inf) = bellman_ford(0, &g);
+        assert_eq!(sp.dist, [Some(0), Some(4), Some(5), Some(7), None]);
+        assert_eq!(sp.path_to(3), Some(vec![0, 1, 2, 3]));
+        assert!(neg_inf.iter().all(|&b| !b));
+        let wf = warshall_froyd(&g);
+        assert!(!wf.has_negative_cycle());
+        assert_eq!(wf.dist[0][3], Some(7));
+        assert_eq!(wf.dist[3][0], None);
+        assert_eq!(wf.path(0, 3), Some(vec![0, 1, 2, 3]));
+        assert_eq!(wf.path(4, 3), Some(vec![4, 3]));
+        assert_eq!(wf.path(2, 2), Some(vec![2]));
+        assert_eq!(wf.path(3, 0), None);
+
+        // 1 -> 2 -> 1 が負閉路で、3はその先
+        let g = Graph::directed(
+            5,
+            vec![(0, 1, 1), (1, 2, -3), (2, 1, 1), (2, 3, 1), (0, 4, 2)],
+        );
+        let (sp, neg_inf) = bellman_ford(0, &g);
+        assert_eq!(sp.dist, [Some(0), None, None, None, Some(2)]);
+        assert_eq!(neg_inf, [false, true, true, true, false]);
+        assert!(warshall_froyd(&g).has_negative_cycle());
+
+        // 大きな負閉路でもあふれない
+        let n = 70;
+        let edges = (0..n)
+            .flat_map(|u| (0..n).filter(move |&v| v != u).map(move |v| (u, v, -1)))
+            .collect();
+        assert!(warshall_froyd(&Graph::directed(n, edges)).has_negative_cycle());
+    }
+
+    #[test]
+    fn negative_edges() {
+        let n = 8;
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..50 {
+            let edges: Vec<_> = (0..14)
+                .map(|_| {
+                    let (u, v) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                    (u, v, rng.gen_range(-2..10))
+                })
+                .collect();
+            let g = Graph::directed(n, edges);
+            let wf = warshall_froyd(&g);
+            match find_negative_cycle(&g) {
+                Some(cycle) => {
+                    assert!(wf.has_negative_cycle());
+                    // 閉路になっていて、重みの和が負
+                    let mut sum = 0;
+                    for i in 0..cycle.len() {
+                        let (u, v) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                        sum += g[u].iter().filter(|e| e.0 == v).map(|e| e.1).min().unwrap();
+                    }
+                    assert!(sum < 0);
+                    assert_eq!(johnson(&g), None);
+                }
+                None => {
+                    assert!(!wf.has_negative_cycle());
+                    assert_eq!(johnson(&g), Some(wf.dist.clone()));
+                    for st in 0..n {
+                        assert_eq!(spfa(st, &g).unwrap().dist, wf.dist[st]);
+                    }
+                }
+            }
+            for st in 0..n {
+                let (sp, neg_inf) = bellman_ford(st, &g);
+                assert_eq!(spfa(st, &g).is_none(), neg_inf.iter().any(|&b| b));
+                if let Some(spfa) = spfa(st, &g) {
+                    assert_eq!(spfa.dist, sp.dist);
+                }
+            }
+        }
+        // isizeに収まらない経路は使わない
+        let g = Graph::directed(3, vec![(0, 1, isize::MAX), (1, 2, isize::MAX)]);
+        assert_eq!(
+            bellman_ford(0, &g).0.dist,
+            [Some(0), Some(isize::MAX), None]
+        );
+        assert_eq!(spfa(0, &g).unwrap().dist, [Some(0), Some(isize::MAX), None]);
+        assert_eq!(find_negative_cycle(&g), None);
+        assert_eq!(johnson(&g).unwrap()[0], [Some(0), Some(isize::MAX), None]);
+        let g = Graph::directed(3, vec![(0, 1, isize::MAX), (2, 1, -3)]);
+        assert_eq!(
+            johnson(&g),
+            Some(vec![
+                vec![Some(0), Some(isize::MAX), None],
+                vec![None, Some(0), None],
+                vec![None, Some(-3), Some(0)],
+            ])
+        );
+    }
+
+    #[test]
+    fn bipartite() {
+        let g = Graph::unweighted(6, &[(0, 1), (1, 2), (2, 3), (4, 5)], false);
+        let iro = isbiparrite(&g).unwrap();
+        assert_eq!(iro, [0, 1, 0, 1, 0, 1]);
+        let g = Graph::unweighted(3, &[(0, 1), (1, 2), (2, 0)], false);
+        assert_eq!(isbiparrite(&g), None);
+    }
+
+    /// (コスト, 辺の本数)の辞書順
+    struct CostLen;
+    impl Monoid for CostLen {
+        type S = (u64, u64);
+        fn op(left: &Self::S, right: &Self::S) -> Self::S {
+            (left.0 + right.0, left.1 + right.1)
+        }
+        fn e() -> Self::S {
+            (0, 0)
+        }
+    }
+
+    #[test]
+    fn generic_dijkstra() {
+        let n = 12;
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let mut edges = vec![];
+        let mut cost = vec![vec![None; n]; n];
+        for _ in 0..30 {
+            let (u, v, c) = (
+                rng.gen_range(0..n),
+                rng.gen_range(0..n),
+                rng.gen_range(0..5),
+            );
+            edges.push((u, v, (c, 1)));
+            if cost[u][v].is_none_or(|x| x > (c, 1)) {
+                cost[u][v] = Some((c, 1));
+            }
+        }
+        let g = Graph::directed(n, edges);
+        let sp = dijkstra_by::<CostLen, _>(&[0, 5], &g, None);
+        assert_eq!(sp.dist, dijkstra_dense::<CostLen>(&[0, 5], &cost).dist);
+        // 素朴なベルマンフォードと比べる
+        let mut dist = vec![None; n];
+        dist[0] = Some((0, 0));
+        dist[5] = Some((0, 0));
+        for _ in 0..n {
+            for &(u, v, c) in g.edges() {
+                if let Some(d) = dist[u] {
+                    let nd = CostLen::op(&d, &c);
+                    if dist[v].is_none_or(|x| x > nd) {
+                        dist[v] = Some(nd);
+                    }
+                }
+            }
+        }
+        assert_eq!(sp.dist, dist);
+        for (v, d) in dist.iter().enumerate() {
+            if let Some(path) = sp.path_to(v) {
+                assert!(path[0] == 0 || path[0] == 5);
+                assert_eq!(d.unwrap().1, path.len() as u64 - 1);
+            }
+        }
+        // 打ち切った頂点までは確定している
+        for (t, d) in dist.iter().enumerate() {
+            assert_eq!(dijkstra_by::<CostLen, _>(&[0, 5], &g, Some(t)).dist[t], *d);
+        }
+    }
+
+    #[test]
+    fn small_weights() {
+        let n = 15;
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        let edges: Vec<_> = (0..40)
+            .map(|_| {
+                (
+                    rng.gen_range(0..n),
+                    rng.gen_range(0..n),
+                    rng.gen_range(0..4),
+                )
+            })
+            .collect();
+        let g = Graph::directed(n, edges.clone());
+        let expected = dijkstra_by::<Additive<usize>, _>(&[0, 3], &g, None).dist;
+        assert_eq!(dial(&[0, 3], &g, 3).dist, expected);
+        let sp = dial(&[0, 3], &g, 5);
+        assert_eq!(sp.dist, expected);
+        for v in 0..n {
+            if let Some(path) = sp.path_to(v) {
+                let len: usize = path
+                    .windows(2)
+                    .map(|e| {
+                        g[e[0]]
+                            .iter()
+                            .filter(|x| x.0 == e[1])
+                            .map(|x| x.1)
+                            .min()
+                            .unwrap()
+                    })
+                    .sum();
+                assert_eq!(Some(len), sp.dist[v]);
+            }
+        }
+
+        let g01 = Graph::directed(n, edges.iter().map(|&(u, v, c)| (u, v, c % 2)).collect());
+        assert_eq!(
+            bfs01(&[0, 3], &g01).dist,
+            dijkstra_by::<Additive<usize>, _>(&[0, 3], &g01, None).dist
+        );
+    }
+
+    #[test]
+    fn count_paths() {
+        // h * wのグリッドを右か下に進む
+        let (h, w) = (5, 6);
+        let mut edges = vec![];
+        for i in 0..h {
+            for j in 0..w {
+                if i + 1 < h {
+                    edges.push((i * w + j, (i + 1) * w + j, 1u64));
+                }
+                if j + 1 < w {
+                    edges.push((i * w + j, i * w + j + 1, 1u64));
+                }
+            }
+        }
+        let g = Graph::undirected(h * w, edges);
+        let (sp, cnt) = count_shortest_paths::<Additive<u64>, _>(&[0], &g, 1_000_000_007);
+        assert_eq!(sp.dist[h * w - 1], Some((h + w - 2) as u64));
+        // C(9, 4)
+        assert_eq!(cnt[h * w - 1], 126);
+        let (_, cnt) = count_shortest_paths::<Additive<u64>, _>(&[0], &g, 7);
+        assert_eq!(cnt[h * w - 1], 126 % 7);
+    }
 
     #[test]
     fn et() {
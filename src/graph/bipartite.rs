@@ -0,0 +1,297 @@
+use cargo_snippet::snippet;
+
+/// 二部グラフの最大マッチング
+///
+/// Hopcroft-Karp法で`O(|E| sqrt(|V|))`
+#[snippet("BipartiteMatching")]
+#[derive(Debug, Clone)]
+pub struct BipartiteMatching {
+    /// 左側の頂点から右側の頂点への辺
+    adj: Vec<Vec<usize>>,
+    match_l: Vec<Option<usize>>,
+    match_r: Vec<Option<usize>>,
+}
+#[snippet("BipartiteMatching")]
+impl BipartiteMatching {
+    pub fn new(l: usize, r: usize) -> Self {
+        //! 左側に`l`頂点、右側に`r`頂点
+        BipartiteMatching {
+            adj: vec![vec![]; l],
+            match_l: vec![None; l],
+            match_r: vec![None; r],
+        }
+    }
+    pub fn add_edge(&mut self, u: usize, v: usize) {
+        //! 左側の`u`と右側の`v`を結ぶ
+        assert!(v < self.match_r.len());
+        self.adj[u].push(v);
+    }
+    pub fn max_matching(&mut self) -> usize {
+        //! 最大マッチングを求め、その大きさを返す
+        let l = self.adj.len();
+        loop {
+            // 空いている左側の頂点からの、交互路での距離
+            let mut dist = vec![usize::MAX; l];
+            let mut q = std::collections::VecDeque::new();
+            for (u, m) in self.match_l.iter().enumerate() {
+                if m.is_none() {
+                    dist[u] = 0;
+                    q.push_back(u);
+                }
+            }
+            // 空いている右側の頂点に着く最短の距離。それより先は探さない
+            let mut limit = usize::MAX;
+            while let Some(u) = q.pop_front() {
+                if dist[u] > limit {
+                    break;
+                }
+                for &v in &self.adj[u] {
+                    match self.match_r[v] {
+                        Some(w) if dist[w] == usize::MAX => {
+                            dist[w] = dist[u] + 1;
+                            q.push_back(w);
+                        }
+                        None => limit = dist[u],
+                        _ => {}
+                    }
+                }
+            }
+            if limit == usize::MAX {
+                break;
+            }
+            let mut iter = vec![0; l];
+            for u in 0..l {
+                if self.match_l[u].is_none() {
+                    self.augment(u, &dist, limit, &mut iter);
+                }
+            }
+        }
+        self.match_l.iter().filter(|m| m.is_some()).count()
+    }
+    fn augment(&mut self, u: usize, dist: &[usize], limit: usize, iter: &mut [usize]) -> bool {
+        //! 最短の増加路だけを使う
+        while iter[u] < self.adj[u].len() {
+            let v = self.adj[u][iter[u]];
+            iter[u] += 1;
+            let ok = match self.match_r[v] {
+                None => dist[u] == limit,
+                Some(w) => dist[w] == dist[u] + 1 && self.augment(w, dist, limit, iter),
+            };
+            if ok {
+                self.match_l[u] = Some(v);
+                self.match_r[v] = Some(u);
+                return true;
+            }
+        }
+        false
+    }
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        //! マッチングに使う辺`(左側, 右側)`
+        (0..self.adj.len())
+            .filter_map(|u| self.match_l[u].map(|v| (u, v)))
+            .collect()
+    }
+    pub fn match_l(&self, u: usize) -> Option<usize> {
+        self.match_l[u]
+    }
+    pub fn match_r(&self, v: usize) -> Option<usize> {
+        self.match_r[v]
+    }
+    fn alternating_reachable(&self) -> (Vec<bool>, Vec<bool>) {
+        //! 空いている左側の頂点から、交互路で到達できる頂点
+        let mut seen_l = vec![false; self.adj.len()];
+        let mut seen_r = vec![false; self.match_r.len()];
+        let mut stack: Vec<usize> = (0..self.adj.len())
+            .filter(|&u| self.match_l[u].is_none())
+            .collect();
+        for &u in &stack {
+            seen_l[u] = true;
+        }
+        while let Some(u) = stack.pop() {
+            for &v in &self.adj[u] {
+                if seen_r[v] {
+                    continue;
+                }
+                seen_r[v] = true;
+                if let Some(w) = self.match_r[v] {
+                    if !seen_l[w] {
+                        seen_l[w] = true;
+                        stack.push(w);
+                    }
+                }
+            }
+        }
+        (seen_l, seen_r)
+    }
+    pub fn min_vertex_cover(&self) -> (Vec<usize>, Vec<usize>) {
+        //! 最小頂点被覆を`(左側の頂点, 右側の頂点)`で返す
+        //!
+        //! `max_matching`の後に呼ぶ。大きさは最大マッチングに等しい (Kőnigの定理)
+        let (seen_l, seen_r) = self.alternating_reachable();
+        (
+            (0..seen_l.len()).filter(|&u| !seen_l[u]).collect(),
+            (0..seen_r.len()).filter(|&v| seen_r[v]).collect(),
+        )
+    }
+    pub fn max_independent_set(&self) -> (Vec<usize>, Vec<usize>) {
+        //! 最大独立集合を`(左側の頂点, 右側の頂点)`で返す
+        //!
+        //! `max_matching`の後に呼ぶ。最小頂点被覆の補集合
+        let (seen_l, seen_r) = self.alternating_reachable();
+        (
+            (0..seen_l.len()).filter(|&u| seen_l[u]).collect(),
+            (0..seen_r.len()).filter(|&v| !seen_r[v]).collect(),
+        )
+    }
+}
+
+#[snippet("hungarian")]
+pub fn hungarian(cost: &[Vec<i64>]) -> (i64, Vec<usize>) {
+    //! `n * m`の費用行列で、各行に相異なる列を割り当てる最小費用と、その割り当てを返す
+    //!
+    //! `n <= m`であること
+    //!
+    //! `O(n^2 m)`
+    let n = cost.len();
+    if n == 0 {
+        return (0, vec![]);
+    }
+    let m = cost[0].len();
+    assert!(n <= m);
+    const INF: i64 = i64::MAX;
+    // 行と列のポテンシャル。列0は番兵
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; m + 1];
+    // p[j]は列jに割り当てた行 (1-indexed、0は未割り当て)
+    let mut p = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![INF; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = INF;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if used[j] {
+                    continue;
+                }
+                let cur = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                if cur < minv[j] {
+                    minv[j] = cur;
+                    way[j] = j0;
+                }
+                if minv[j] < delta {
+                    delta = minv[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        // 増加路に沿って割り当てを入れ替える
+        while j0 != 0 {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+        }
+    }
+    let mut assign = vec![0; n];
+    for j in 1..=m {
+        if p[j] != 0 {
+            assign[p[j] - 1] = j - 1;
+        }
+    }
+    let total = (0..n).map(|i| cost[i][assign[i]]).sum();
+    (total, assign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+    use rand::Rng;
+
+    #[test]
+    fn matching() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..100 {
+            let (l, r) = (rng.gen_range(1..5), rng.gen_range(1..5));
+            let mut edges = vec![];
+            for u in 0..l {
+                for v in 0..r {
+                    if rng.gen_range(0..3) == 0 {
+                        edges.push((u, v));
+                    }
+                }
+            }
+            let mut bm = BipartiteMatching::new(l, r);
+            for &(u, v) in &edges {
+                bm.add_edge(u, v);
+            }
+            let size = bm.max_matching();
+            // 辺の部分集合を全て試す
+            let brute = (0..1usize << edges.len())
+                .filter(|s| {
+                    let chosen: Vec<_> = (0..edges.len()).filter(|i| s >> i & 1 == 1).collect();
+                    chosen.iter().map(|&i| edges[i].0).all_unique()
+                        && chosen.iter().map(|&i| edges[i].1).all_unique()
+                })
+                .map(|s| s.count_ones() as usize)
+                .max()
+                .unwrap();
+            assert_eq!(size, brute);
+            let pairs = bm.pairs();
+            assert_eq!(pairs.len(), size);
+            for &(u, v) in &pairs {
+                assert!(edges.contains(&(u, v)));
+                assert_eq!((bm.match_l(u), bm.match_r(v)), (Some(v), Some(u)));
+            }
+
+            let (cl, cr) = bm.min_vertex_cover();
+            assert_eq!(cl.len() + cr.len(), size);
+            for &(u, v) in &edges {
+                assert!(cl.contains(&u) || cr.contains(&v));
+            }
+            let (il, ir) = bm.max_independent_set();
+            assert_eq!(il.len() + ir.len(), l + r - size);
+            for &(u, v) in &edges {
+                assert!(!(il.contains(&u) && ir.contains(&v)));
+            }
+        }
+    }
+
+    #[test]
+    fn assignment() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..50 {
+            let n = rng.gen_range(1..6);
+            let m = n + rng.gen_range(0..3);
+            let cost: Vec<Vec<i64>> = (0..n)
+                .map(|_| (0..m).map(|_| rng.gen_range(-10..=10)).collect())
+                .collect();
+            let (total, assign) = hungarian(&cost);
+            assert!(assign.iter().all_unique());
+            assert_eq!(total, (0..n).map(|i| cost[i][assign[i]]).sum::<i64>());
+            let brute = (0..m)
+                .permutations(n)
+                .map(|p| (0..n).map(|i| cost[i][p[i]]).sum::<i64>())
+                .min()
+                .unwrap();
+            assert_eq!(total, brute);
+        }
+    }
+}
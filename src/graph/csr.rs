@@ -0,0 +1,257 @@
+use cargo_snippet::snippet;
+
+/// 頂点`v`から出る辺を、`(行き先, 重み)`で列挙できるグラフ
+///
+/// `graph`のアルゴリズムはこれを受け取る
+#[snippet("AdjacencyList")]
+pub trait AdjacencyList {
+    type W;
+    /// 頂点数
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &Self::W)> + '_;
+}
+/// 重みなしの隣接リスト
+#[snippet("AdjacencyList")]
+impl AdjacencyList for [Vec<usize>] {
+    type W = ();
+    fn len(&self) -> usize {
+        <[Vec<usize>]>::len(self)
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &())> + '_ {
+        self[v].iter().map(|&nv| (nv, &()))
+    }
+}
+#[snippet("AdjacencyList")]
+impl AdjacencyList for Vec<Vec<usize>> {
+    type W = ();
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &())> + '_ {
+        self.as_slice().neighbors(v)
+    }
+}
+/// `(cost, nv)`の隣接リスト
+#[snippet("AdjacencyList")]
+impl<C> AdjacencyList for [Vec<(C, usize)>] {
+    type W = C;
+    fn len(&self) -> usize {
+        <[Vec<(C, usize)>]>::len(self)
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &C)> + '_ {
+        self[v].iter().map(|(c, nv)| (*nv, c))
+    }
+}
+#[snippet("AdjacencyList")]
+impl<C> AdjacencyList for Vec<Vec<(C, usize)>> {
+    type W = C;
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &C)> + '_ {
+        self.as_slice().neighbors(v)
+    }
+}
+
+/// CSR形式で持つグラフ
+///
+/// 無向グラフは、各辺を両向きの有向辺として持つ
+#[snippet("Graph")]
+#[snippet(include = "AdjacencyList")]
+#[derive(Debug, Clone)]
+pub struct Graph<W> {
+    directed: bool,
+    /// 頂点`v`から出る辺は`adj[start[v]..start[v + 1]]`
+    start: Vec<usize>,
+    adj: Vec<(usize, W)>,
+    /// 入力された辺`(u, v, w)`
+    edges: Vec<(usize, usize, W)>,
+}
+#[snippet("Graph")]
+impl<W> Graph<W>
+where
+    W: Clone,
+{
+    pub fn new(n: usize, edges: Vec<(usize, usize, W)>, directed: bool) -> Self {
+        //! 0-indexedの辺`(u, v, w)`のリストから構築する
+        //!
+        //! `O(|V| + |E|)`
+        let mut start = vec![0; n + 1];
+        for &(u, v, _) in &edges {
+            assert!(u < n && v < n);
+            start[u + 1] += 1;
+            if !directed {
+                start[v + 1] += 1;
+            }
+        }
+        for i in 0..n {
+            start[i + 1] += start[i];
+        }
+        let mut pos = start.clone();
+        let mut adj = vec![None; start[n]];
+        for (u, v, w) in &edges {
+            adj[pos[*u]] = Some((*v, w.clone()));
+            pos[*u] += 1;
+            if !directed {
+                adj[pos[*v]] = Some((*u, w.clone()));
+                pos[*v] += 1;
+            }
+        }
+        Graph {
+            directed,
+            start,
+            adj: adj.into_iter().map(Option::unwrap).collect(),
+            edges,
+        }
+    }
+    pub fn directed(n: usize, edges: Vec<(usize, usize, W)>) -> Self {
+        Self::new(n, edges, true)
+    }
+    pub fn undirected(n: usize, edges: Vec<(usize, usize, W)>) -> Self {
+        Self::new(n, edges, false)
+    }
+    pub fn from_one_indexed(n: usize, edges: Vec<(usize, usize, W)>, directed: bool) -> Self {
+        //! 1-indexedの辺のリストから構築する
+        let edges = edges
+            .into_iter()
+            .map(|(u, v, w)| (u - 1, v - 1, w))
+            .collect();
+        Self::new(n, edges, directed)
+    }
+    pub fn read<R, S>(source: &mut S, n: usize, m: usize, directed: bool, one_indexed: bool) -> Self
+    where
+        R: std::io::BufRead,
+        S: proconio::source::Source<R>,
+        W: proconio::source::Readable<Output = W>,
+    {
+        //! `u v w`の形の`m`行を読んで構築する
+        //!
+        //! `input!`とは入力を共有できないので、`source`を自分で作って両方に渡す
+        //!
+        //! `let mut source = LineSource::new(BufReader::new(stdin()));`を作り、
+        //! `input! { from &mut source, n: usize, m: usize }`の後に`Graph::read(&mut source, n, m, ...)`とする
+        //!
+        //! 入力全体を先に読むなら`OnceSource`でもよい
+        use proconio::source::Readable;
+        let offset = one_indexed as usize;
+        let edges = (0..m)
+            .map(|_| {
+                let u = usize::read(source) - offset;
+                let v = usize::read(source) - offset;
+                (u, v, W::read(source))
+            })
+            .collect();
+        Self::new(n, edges, directed)
+    }
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+    pub fn edges(&self) -> &[(usize, usize, W)] {
+        //! 入力された辺`(u, v, w)`のリスト
+        &self.edges
+    }
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+    pub fn reversed(&self) -> Self {
+        //! 全ての辺を逆向きにしたグラフ
+        let edges = self
+            .edges
+            .iter()
+            .map(|(u, v, w)| (*v, *u, w.clone()))
+            .collect();
+        Self::new(self.len(), edges, self.directed)
+    }
+}
+#[snippet("Graph")]
+impl Graph<()> {
+    pub fn unweighted(n: usize, edges: &[(usize, usize)], directed: bool) -> Self {
+        //! 0-indexedの重みなしの辺`(u, v)`のリストから構築する
+        Self::new(
+            n,
+            edges.iter().map(|&(u, v)| (u, v, ())).collect(),
+            directed,
+        )
+    }
+    pub fn read_unweighted<R, S>(
+        source: &mut S,
+        n: usize,
+        m: usize,
+        directed: bool,
+        one_indexed: bool,
+    ) -> Self
+    where
+        R: std::io::BufRead,
+        S: proconio::source::Source<R>,
+    {
+        //! `u v`の形の`m`行を読んで構築する
+        //!
+        //! `source`の作り方は`read`と同じ
+        use proconio::source::Readable;
+        let offset = one_indexed as usize;
+        let edges: Vec<_> = (0..m)
+            .map(|_| (usize::read(source) - offset, usize::read(source) - offset))
+            .collect();
+        Self::unweighted(n, &edges, directed)
+    }
+}
+#[snippet("Graph")]
+impl<W> std::ops::Index<usize> for Graph<W> {
+    type Output = [(usize, W)];
+    fn index(&self, v: usize) -> &Self::Output {
+        &self.adj[self.start[v]..self.start[v + 1]]
+    }
+}
+#[snippet("Graph")]
+impl<W> AdjacencyList for Graph<W> {
+    type W = W;
+    fn len(&self) -> usize {
+        self.start.len() - 1
+    }
+    fn neighbors(&self, v: usize) -> impl DoubleEndedIterator<Item = (usize, &W)> + '_ {
+        self[v].iter().map(|(nv, w)| (*nv, w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proconio::{
+        input,
+        source::{line::LineSource, once::OnceSource},
+    };
+
+    #[test]
+    fn graph() {
+        let g = Graph::undirected(4, vec![(0, 1, 5), (1, 2, 3), (0, 3, 1)]);
+        assert_eq!(g.len(), 4);
+        assert_eq!(g.edge_count(), 3);
+        assert_eq!(g[0], [(1, 5), (3, 1)]);
+        assert_eq!(g[1], [(0, 5), (2, 3)]);
+        assert_eq!(g.neighbors(3).collect::<Vec<_>>(), [(0, &1)]);
+
+        let g = Graph::directed(3, vec![(0, 1, 'a'), (0, 2, 'b'), (2, 1, 'c')]);
+        assert_eq!(g[0], [(1, 'a'), (2, 'b')]);
+        assert!(g[1].is_empty());
+        assert_eq!(g.reversed()[1], [(0, 'a'), (2, 'c')]);
+
+        let mut source = OnceSource::from("1 2 10\n2 3 20\n");
+        let g = Graph::<i64>::read(&mut source, 3, 2, false, true);
+        assert_eq!(g[1], [(0, 10), (2, 20)]);
+        assert_eq!(g.edges(), [(0, 1, 10), (1, 2, 20)]);
+
+        // input!と同じsourceから続けて読む
+        let mut source = LineSource::new(std::io::BufReader::new(&b"2 1\n1 2 7\n"[..]));
+        input! { from &mut source, n: usize, m: usize }
+        let g = Graph::<i64>::read(&mut source, n, m, true, true);
+        assert_eq!(g[0], [(1, 7)]);
+
+        let mut source = OnceSource::from("0 1\n1 2\n");
+        let g = Graph::read_unweighted(&mut source, 3, 2, true, false);
+        assert_eq!(g[0], [(1, ())]);
+        assert_eq!(g.neighbors(1).map(|(nv, _)| nv).collect::<Vec<_>>(), [2]);
+    }
+}
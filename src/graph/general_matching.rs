@@ -0,0 +1,192 @@
+use cargo_snippet::snippet;
+
+/// 一般グラフの最大マッチング
+///
+/// Edmondsの花アルゴリズムで`O(|V|^3)`
+#[snippet("GeneralMatching")]
+#[derive(Debug, Clone)]
+pub struct GeneralMatching {
+    g: Vec<Vec<usize>>,
+    mate: Vec<Option<usize>>,
+}
+#[snippet("GeneralMatching")]
+impl GeneralMatching {
+    pub fn new(n: usize) -> Self {
+        GeneralMatching {
+            g: vec![vec![]; n],
+            mate: vec![None; n],
+        }
+    }
+    pub fn add_edge(&mut self, u: usize, v: usize) {
+        //! 無向辺`(u, v)`を追加する
+        if u == v {
+            return;
+        }
+        self.g[u].push(v);
+        self.g[v].push(u);
+    }
+    pub fn max_matching(&mut self) -> usize {
+        //! 最大マッチングを求め、その大きさを返す
+        let n = self.g.len();
+        for root in 0..n {
+            if self.mate[root].is_some() {
+                continue;
+            }
+            if let Some((v, p)) = self.find_path(root) {
+                // 増加路に沿ってマッチングを入れ替える
+                let mut v = Some(v);
+                while let Some(x) = v {
+                    let px = p[x].unwrap();
+                    let ppx = self.mate[px];
+                    self.mate[x] = Some(px);
+                    self.mate[px] = Some(x);
+                    v = ppx;
+                }
+            }
+        }
+        self.mate.iter().filter(|m| m.is_some()).count() / 2
+    }
+    pub fn mate(&self) -> &[Option<usize>] {
+        //! 各頂点とマッチした頂点
+        &self.mate
+    }
+    fn find_path(&self, root: usize) -> Option<(usize, Vec<Option<usize>>)> {
+        //! `root`からの増加路の終点と、交互木の親を返す
+        let n = self.g.len();
+        let mut used = vec![false; n];
+        let mut p = vec![None; n];
+        // 縮約した花の代表
+        let mut base: Vec<usize> = (0..n).collect();
+        let mut q = std::collections::VecDeque::new();
+        used[root] = true;
+        q.push_back(root);
+        while let Some(v) = q.pop_front() {
+            for &to in &self.g[v] {
+                if base[v] == base[to] || self.mate[v] == Some(to) {
+                    continue;
+                }
+                if to == root || self.mate[to].is_some_and(|m| p[m].is_some()) {
+                    // 奇閉路を見つけたので、花を縮約する
+                    let cur = self.lca(v, to, &base, &p);
+                    let mut blossom = vec![false; n];
+                    self.mark_path(v, cur, to, &base, &mut p, &mut blossom);
+                    self.mark_path(to, cur, v, &base, &mut p, &mut blossom);
+                    for i in 0..n {
+                        if blossom[base[i]] {
+                            base[i] = cur;
+                            if !used[i] {
+                                used[i] = true;
+                                q.push_back(i);
+                            }
+                        }
+                    }
+                } else if p[to].is_none() {
+                    p[to] = Some(v);
+                    match self.mate[to] {
+                        None => return Some((to, p)),
+                        Some(m) => {
+                            used[m] = true;
+                            q.push_back(m);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn lca(&self, a: usize, b: usize, base: &[usize], p: &[Option<usize>]) -> usize {
+        //! 交互木で`a`と`b`の共通祖先になる花の代表
+        let mut used = vec![false; self.g.len()];
+        let mut a = a;
+        loop {
+            a = base[a];
+            used[a] = true;
+            match self.mate[a] {
+                None => break,
+                Some(m) => a = p[m].unwrap(),
+            }
+        }
+        let mut b = b;
+        loop {
+            b = base[b];
+            if used[b] {
+                return b;
+            }
+            b = p[self.mate[b].unwrap()].unwrap();
+        }
+    }
+    fn mark_path(
+        &self,
+        v: usize,
+        b: usize,
+        child: usize,
+        base: &[usize],
+        p: &mut [Option<usize>],
+        blossom: &mut [bool],
+    ) {
+        //! `v`から花の代表`b`までの頂点を花に含め、親を付け替える
+        let (mut v, mut child) = (v, child);
+        while base[v] != b {
+            let m = self.mate[v].unwrap();
+            blossom[base[v]] = true;
+            blossom[base[m]] = true;
+            p[v] = Some(child);
+            child = m;
+            v = p[m].unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn brute(edges: &[(usize, usize)], used: &mut [bool], i: usize) -> usize {
+        if i == edges.len() {
+            return 0;
+        }
+        let mut ret = brute(edges, used, i + 1);
+        let (u, v) = edges[i];
+        if u != v && !used[u] && !used[v] {
+            used[u] = true;
+            used[v] = true;
+            ret = ret.max(1 + brute(edges, used, i + 1));
+            used[u] = false;
+            used[v] = false;
+        }
+        ret
+    }
+
+    #[test]
+    fn general_matching() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..300 {
+            let n = rng.gen_range(1..10);
+            let m = rng.gen_range(0..16);
+            let edges: Vec<_> = (0..m)
+                .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n)))
+                .collect();
+            let mut gm = GeneralMatching::new(n);
+            for &(u, v) in &edges {
+                gm.add_edge(u, v);
+            }
+            let size = gm.max_matching();
+            assert_eq!(size, brute(&edges, &mut vec![false; n], 0));
+            let mate = gm.mate();
+            assert_eq!(mate.iter().filter(|m| m.is_some()).count(), 2 * size);
+            for (v, m) in mate.iter().enumerate() {
+                if let Some(u) = *m {
+                    assert_eq!(mate[u], Some(v));
+                    assert!(edges.contains(&(u, v)) || edges.contains(&(v, u)));
+                }
+            }
+        }
+        // 5頂点の奇閉路に1本ぶら下がっている
+        let mut gm = GeneralMatching::new(6);
+        for (u, v) in [(0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (0, 5)] {
+            gm.add_edge(u, v);
+        }
+        assert_eq!(gm.max_matching(), 3);
+    }
+}
@@ -0,0 +1,142 @@
+use cargo_snippet::snippet;
+
+/// 上下左右
+#[snippet("Grid")]
+pub const DIR4: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+/// 上下左右と斜め
+#[snippet("Grid")]
+pub const DIR8: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// `Vec<Vec<char>>`で与えられる盤面
+///
+/// 壁の文字のマスには入れない
+#[snippet("Grid")]
+#[derive(Debug, Clone)]
+pub struct Grid {
+    h: usize,
+    w: usize,
+    cells: Vec<Vec<char>>,
+    wall: char,
+}
+#[snippet("Grid")]
+impl Grid {
+    pub fn new(cells: Vec<Vec<char>>, wall: char) -> Self {
+        let h = cells.len();
+        let w = if h == 0 { 0 } else { cells[0].len() };
+        assert!(cells.iter().all(|row| row.len() == w));
+        Grid { h, w, cells, wall }
+    }
+    pub fn h(&self) -> usize {
+        self.h
+    }
+    pub fn w(&self) -> usize {
+        self.w
+    }
+    pub fn is_wall(&self, (i, j): (usize, usize)) -> bool {
+        self.cells[i][j] == self.wall
+    }
+    pub fn find(&self, c: char) -> Option<(usize, usize)> {
+        //! 文字`c`のマスを一つ返す
+        self.find_all(c).next()
+    }
+    pub fn find_all(&self, c: char) -> impl Iterator<Item = (usize, usize)> + '_ {
+        //! 文字`c`のマスを行優先の順に返す
+        (0..self.h)
+            .flat_map(move |i| (0..self.w).map(move |j| (i, j)))
+            .filter(move |&(i, j)| self.cells[i][j] == c)
+    }
+    pub fn id(&self, (i, j): (usize, usize)) -> usize {
+        //! マスを`i * w + j`の頂点番号にする
+        i * self.w + j
+    }
+    pub fn neighbors<'a>(
+        &'a self,
+        (i, j): (usize, usize),
+        dirs: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        //! `(i, j)`から`dirs`の向きに進んだ、盤面内の壁でないマス
+        dirs.iter().filter_map(move |&(di, dj)| {
+            let ni = i.checked_add_signed(di)?;
+            let nj = j.checked_add_signed(dj)?;
+            (ni < self.h && nj < self.w && !self.is_wall((ni, nj))).then_some((ni, nj))
+        })
+    }
+    pub fn bfs(
+        &self,
+        starts: &[(usize, usize)],
+        dirs: &[(isize, isize)],
+    ) -> Vec<Vec<Option<usize>>> {
+        //! `starts`からの最短距離
+        //!
+        //! 到達できないマスや壁は`None`
+        //!
+        //! `O(HW |dirs|)`
+        let mut dist = vec![vec![None; self.w]; self.h];
+        let mut q = std::collections::VecDeque::new();
+        for &(i, j) in starts {
+            if dist[i][j].is_none() {
+                dist[i][j] = Some(0);
+                q.push_back((i, j));
+            }
+        }
+        while let Some((i, j)) = q.pop_front() {
+            let d = dist[i][j].unwrap();
+            for (ni, nj) in self.neighbors((i, j), dirs) {
+                if dist[ni][nj].is_some() {
+                    continue;
+                }
+                dist[ni][nj] = Some(d + 1);
+                q.push_back((ni, nj));
+            }
+        }
+        dist
+    }
+}
+#[snippet("Grid")]
+impl std::ops::Index<(usize, usize)> for Grid {
+    type Output = char;
+    fn index(&self, (i, j): (usize, usize)) -> &char {
+        &self.cells[i][j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid() {
+        let s = ["S..#.", ".#.#.", ".#...", "...#G"];
+        let grid = Grid::new(s.iter().map(|r| r.chars().collect()).collect(), '#');
+        assert_eq!((grid.h(), grid.w()), (4, 5));
+        let st = grid.find('S').unwrap();
+        let gl = grid.find('G').unwrap();
+        assert_eq!((st, gl), ((0, 0), (3, 4)));
+        assert_eq!(grid[gl], 'G');
+        assert_eq!(grid.id(gl), 19);
+        assert_eq!(grid.find_all('#').count(), 5);
+
+        let mut nb: Vec<_> = grid.neighbors((1, 2), &DIR4).collect();
+        nb.sort();
+        assert_eq!(nb, [(0, 2), (2, 2)]);
+        assert_eq!(grid.neighbors((0, 0), &DIR8).count(), 2);
+
+        let dist = grid.bfs(&[st], &DIR4);
+        assert_eq!(dist[gl.0][gl.1], Some(7));
+        assert_eq!(dist[1][1], None);
+        assert_eq!(grid.bfs(&[st], &DIR8)[gl.0][gl.1], Some(4));
+        // 多始点
+        let dist = grid.bfs(&[st, gl], &DIR4);
+        assert_eq!(dist[0][4], Some(3));
+        assert_eq!(dist[3][2], Some(4));
+    }
+}
@@ -0,0 +1,291 @@
+use cargo_snippet::snippet;
+
+/// 無向グラフのlowlink
+///
+/// 橋、関節点、二重辺連結成分、二重頂点連結成分を求める
+///
+/// 多重辺があってもよい。自己ループは無視する
+///
+/// `O(|V| + |E|)`
+#[snippet("LowLink")]
+#[derive(Debug, Clone)]
+pub struct LowLink {
+    edges: Vec<(usize, usize)>,
+    /// `(行き先, 辺の番号)`
+    g: Vec<Vec<(usize, usize)>>,
+    /// DFSでの訪問順
+    pub ord: Vec<usize>,
+    /// 後退辺を高々1本使って行ける頂点の`ord`の最小値
+    pub low: Vec<usize>,
+    /// 橋の辺の番号
+    pub bridges: Vec<usize>,
+    /// 関節点
+    pub articulations: Vec<usize>,
+    /// 二重頂点連結成分ごとの辺の番号
+    pub bcc_edges: Vec<Vec<usize>>,
+}
+#[snippet("LowLink")]
+impl LowLink {
+    pub fn new(n: usize, edges: Vec<(usize, usize)>) -> Self {
+        //! 頂点数`n`、`i`番目の辺が`edges[i]`の無向グラフ
+        let mut g = vec![vec![]; n];
+        for (i, &(u, v)) in edges.iter().enumerate() {
+            if u == v {
+                continue;
+            }
+            g[u].push((v, i));
+            g[v].push((u, i));
+        }
+        let mut ll = LowLink {
+            edges,
+            g,
+            ord: vec![usize::MAX; n],
+            low: vec![usize::MAX; n],
+            bridges: vec![],
+            articulations: vec![],
+            bcc_edges: vec![],
+        };
+        let mut k = 0;
+        let mut stack = vec![];
+        for v in 0..n {
+            if ll.ord[v] == usize::MAX {
+                ll.dfs(v, None, &mut k, &mut stack);
+            }
+        }
+        ll.articulations.sort();
+        ll
+    }
+    fn dfs(&mut self, v: usize, pe: Option<usize>, k: &mut usize, stack: &mut Vec<usize>) {
+        self.ord[v] = *k;
+        self.low[v] = *k;
+        *k += 1;
+        let mut children = 0;
+        let mut is_articulation = false;
+        for i in 0..self.g[v].len() {
+            let (nv, id) = self.g[v][i];
+            // 親への辺は、同じ番号の辺だけを除く
+            if Some(id) == pe {
+                continue;
+            }
+            if self.ord[nv] == usize::MAX {
+                children += 1;
+                stack.push(id);
+                self.dfs(nv, Some(id), k, stack);
+                self.low[v] = self.low[v].min(self.low[nv]);
+                if self.low[nv] > self.ord[v] {
+                    self.bridges.push(id);
+                }
+                if self.low[nv] >= self.ord[v] {
+                    is_articulation |= pe.is_some();
+                    let mut comp = vec![];
+                    while let Some(e) = stack.pop() {
+                        comp.push(e);
+                        if e == id {
+                            break;
+                        }
+                    }
+                    self.bcc_edges.push(comp);
+                }
+            } else if self.ord[nv] < self.ord[v] {
+                stack.push(id);
+                self.low[v] = self.low[v].min(self.ord[nv]);
+            }
+        }
+        if is_articulation || (pe.is_none() && children >= 2) {
+            self.articulations.push(v);
+        }
+    }
+    pub fn is_bridge(&self, id: usize) -> bool {
+        //! `id`番目の辺が橋か
+        let (u, v) = self.edges[id];
+        if u == v {
+            return false;
+        }
+        // 子の側から、後退辺で親以上に行けない
+        let (p, c) = if self.ord[u] < self.ord[v] {
+            (u, v)
+        } else {
+            (v, u)
+        };
+        self.low[c] > self.ord[p]
+    }
+    pub fn two_edge_connected_components(&self) -> (Vec<usize>, Vec<Vec<usize>>) {
+        //! 二重辺連結成分の番号と、成分を頂点とし橋を辺とする森を返す
+        let n = self.g.len();
+        let mut is_bridge = vec![false; self.edges.len()];
+        for &id in &self.bridges {
+            is_bridge[id] = true;
+        }
+        let mut comp = vec![usize::MAX; n];
+        let mut cnt = 0;
+        for s in 0..n {
+            if comp[s] != usize::MAX {
+                continue;
+            }
+            comp[s] = cnt;
+            let mut stack = vec![s];
+            while let Some(v) = stack.pop() {
+                for &(nv, id) in &self.g[v] {
+                    if is_bridge[id] || comp[nv] != usize::MAX {
+                        continue;
+                    }
+                    comp[nv] = cnt;
+                    stack.push(nv);
+                }
+            }
+            cnt += 1;
+        }
+        let mut tree = vec![vec![]; cnt];
+        for &id in &self.bridges {
+            let (u, v) = self.edges[id];
+            tree[comp[u]].push(comp[v]);
+            tree[comp[v]].push(comp[u]);
+        }
+        (comp, tree)
+    }
+    pub fn biconnected_components(&self) -> Vec<Vec<usize>> {
+        //! 二重頂点連結成分ごとの頂点
+        //!
+        //! 孤立点は1頂点の成分になる
+        let n = self.g.len();
+        let mut seen = vec![usize::MAX; n];
+        let mut ret = vec![];
+        for (i, es) in self.bcc_edges.iter().enumerate() {
+            let mut vs = vec![];
+            for &id in es {
+                let (u, v) = self.edges[id];
+                for x in [u, v] {
+                    if seen[x] != i {
+                        seen[x] = i;
+                        vs.push(x);
+                    }
+                }
+            }
+            vs.sort();
+            ret.push(vs);
+        }
+        for v in 0..n {
+            if self.g[v].is_empty() {
+                ret.push(vec![v]);
+            }
+        }
+        ret
+    }
+    pub fn block_cut_tree(&self) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+        //! 頂点`0..n`と、二重頂点連結成分`n..`を頂点とする森と、各成分の頂点を返す
+        //!
+        //! 頂点と、それを含む成分の間に辺を張る。関節点でない頂点は葉になる
+        let n = self.g.len();
+        let bcc = self.biconnected_components();
+        let mut tree = vec![vec![]; n + bcc.len()];
+        for (i, vs) in bcc.iter().enumerate() {
+            for &v in vs {
+                tree[v].push(n + i);
+                tree[n + i].push(v);
+            }
+        }
+        (tree, bcc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::unionfind::UnionFind;
+    use rand::Rng;
+
+    /// 頂点`skip_v`と辺`skip_e`を除いたときの連結性
+    fn connectivity(
+        n: usize,
+        edges: &[(usize, usize)],
+        skip_v: Option<usize>,
+        skip_e: Option<usize>,
+    ) -> UnionFind {
+        let mut uf = UnionFind::new(n);
+        for (i, &(u, v)) in edges.iter().enumerate() {
+            if Some(i) == skip_e || Some(u) == skip_v || Some(v) == skip_v {
+                continue;
+            }
+            uf.unite(u, v);
+        }
+        uf
+    }
+
+    #[test]
+    fn lowlink() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..200 {
+            let n = rng.gen_range(1..9);
+            let edges: Vec<_> = (0..rng.gen_range(0..12))
+                .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n)))
+                .filter(|&(u, v)| u != v)
+                .collect();
+            let m = edges.len();
+            let ll = LowLink::new(n, edges.clone());
+            let mut all = connectivity(n, &edges, None, None);
+            let groups = all.group_count();
+
+            for i in 0..m {
+                let mut uf = connectivity(n, &edges, None, Some(i));
+                assert_eq!(ll.bridges.contains(&i), uf.group_count() > groups);
+                assert_eq!(ll.is_bridge(i), uf.group_count() > groups);
+            }
+            let arts: Vec<_> = (0..n)
+                .filter(|&v| {
+                    let mut uf = connectivity(n, &edges, Some(v), None);
+                    // v自身が孤立点として1つ数えられる
+                    uf.group_count() > groups + 1
+                })
+                .collect();
+            assert_eq!(ll.articulations, arts);
+
+            // どの辺を除いても連結なら、同じ二重辺連結成分
+            let (comp, tree) = ll.two_edge_connected_components();
+            for u in 0..n {
+                for v in 0..n {
+                    let same = all.issame(u, v)
+                        && (0..m).all(|i| connectivity(n, &edges, None, Some(i)).issame(u, v));
+                    assert_eq!(comp[u] == comp[v], same);
+                }
+            }
+            let k = tree.len();
+            assert_eq!(
+                tree.iter().map(|t| t.len()).sum::<usize>(),
+                2 * ll.bridges.len()
+            );
+            assert_eq!(k - ll.bridges.len(), groups);
+
+            // どの頂点を除いても両端が連結なら、同じ二重頂点連結成分
+            let mut bcc_id = vec![usize::MAX; m];
+            for (i, es) in ll.bcc_edges.iter().enumerate() {
+                for &e in es {
+                    assert_eq!(bcc_id[e], usize::MAX);
+                    bcc_id[e] = i;
+                }
+            }
+            for e in 0..m {
+                for f in 0..m {
+                    let same = (0..n).all(|x| {
+                        let mut uf = connectivity(n, &edges, Some(x), None);
+                        let (a, b) = edges[e];
+                        let (c, d) = edges[f];
+                        let ends_e = [a, b].into_iter().filter(|&y| y != x);
+                        let ends_f: Vec<_> = [c, d].into_iter().filter(|&y| y != x).collect();
+                        ends_e
+                            .flat_map(|y| ends_f.iter().map(move |&z| (y, z)))
+                            .any(|(y, z)| uf.issame(y, z))
+                    });
+                    assert_eq!(bcc_id[e] == bcc_id[f], same);
+                }
+            }
+            let (bct, bcc) = ll.block_cut_tree();
+            assert_eq!(bct.len(), n + bcc.len());
+            for (v, adj) in bct.iter().take(n).enumerate() {
+                assert_eq!(adj.len() >= 2, ll.articulations.contains(&v));
+            }
+            // 森なので、辺の数は頂点数から連結成分の数を引いたもの
+            let bct_edges = bct.iter().map(|t| t.len()).sum::<usize>() / 2;
+            assert_eq!(bct_edges, bct.len() - groups);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use cargo_snippet::snippet;
+
+/// 辺の状態
+#[snippet("MaxFlow")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowEdge {
+    pub from: usize,
+    pub to: usize,
+    pub cap: i64,
+    pub flow: i64,
+}
+
+#[snippet("MaxFlow")]
+#[derive(Debug, Clone)]
+struct InnerEdge {
+    to: usize,
+    /// `g[to]`での逆辺の位置
+    rev: usize,
+    /// 残余容量
+    cap: i64,
+}
+
+/// 最大流
+///
+/// `flow`はDinic法で`O(|V|^2 |E|)`、`push_relabel`は最高ラベル優先のpush-relabel法で`O(|V|^2 sqrt(|E|))`
+#[snippet("MaxFlow")]
+#[derive(Debug, Clone)]
+pub struct MaxFlow {
+    g: Vec<Vec<InnerEdge>>,
+    /// `add_edge`で追加した辺の、`(from, g[from]での位置)`
+    pos: Vec<(usize, usize)>,
+}
+#[snippet("MaxFlow")]
+impl MaxFlow {
+    pub fn new(n: usize) -> Self {
+        MaxFlow {
+            g: vec![vec![]; n],
+            pos: vec![],
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.g.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.g.is_empty()
+    }
+    pub fn add_edge(&mut self, from: usize, to: usize, cap: i64) -> usize {
+        //! 容量`cap`の辺を追加し、その番号を返す
+        assert!(0 <= cap);
+        let id = self.pos.len();
+        let from_idx = self.g[from].len();
+        // 自己ループでは逆辺が同じ頂点の次の位置に入る
+        let to_idx = self.g[to].len() + (from == to) as usize;
+        self.pos.push((from, from_idx));
+        self.g[from].push(InnerEdge {
+            to,
+            rev: to_idx,
+            cap,
+        });
+        self.g[to].push(InnerEdge {
+            to: from,
+            rev: from_idx,
+            cap: 0,
+        });
+        id
+    }
+    pub fn get_edge(&self, id: usize) -> FlowEdge {
+        let (from, idx) = self.pos[id];
+        let e = &self.g[from][idx];
+        let re = &self.g[e.to][e.rev];
+        FlowEdge {
+            from,
+            to: e.to,
+            cap: e.cap + re.cap,
+            flow: re.cap,
+        }
+    }
+    pub fn edges(&self) -> Vec<FlowEdge> {
+        (0..self.pos.len()).map(|id| self.get_edge(id)).collect()
+    }
+    pub fn change_edge(&mut self, id: usize, new_cap: i64, new_flow: i64) {
+        //! 辺`id`の容量と流量を書き換える
+        assert!(0 <= new_flow && new_flow <= new_cap);
+        let (from, idx) = self.pos[id];
+        let (to, rev) = (self.g[from][idx].to, self.g[from][idx].rev);
+        self.g[from][idx].cap = new_cap - new_flow;
+        self.g[to][rev].cap = new_flow;
+    }
+    pub fn flow(&mut self, s: usize, t: usize) -> i64 {
+        //! `s`から`t`へ流せるだけ流し、流した量を返す
+        self.flow_with_limit(s, t, i64::MAX)
+    }
+    pub fn flow_with_limit(&mut self, s: usize, t: usize, limit: i64) -> i64 {
+        //! Dinic法で`s`から`t`へ`limit`まで流し、流した量を返す
+        assert_ne!(s, t);
+        let n = self.len();
+        let mut level = vec![0; n];
+        let mut iter = vec![0; n];
+        let mut flow = 0;
+        while flow < limit {
+            // 残余グラフでsからの距離を求める
+            level.fill(usize::MAX);
+            level[s] = 0;
+            let mut q = std::collections::VecDeque::new();
+            q.push_back(s);
+            while let Some(v) = q.pop_front() {
+                for e in &self.g[v] {
+                    if e.cap > 0 && level[e.to] == usize::MAX {
+                        level[e.to] = level[v] + 1;
+                        q.push_back(e.to);
+                    }
+                }
+            }
+            if level[t] == usize::MAX {
+                break;
+            }
+            iter.fill(0);
+            while flow < limit {
+                let f = self.dfs(s, t, limit - flow, &level, &mut iter);
+                if f == 0 {
+                    break;
+                }
+                flow += f;
+            }
+        }
+        flow
+    }
+    fn dfs(&mut self, v: usize, t: usize, up: i64, level: &[usize], iter: &mut [usize]) -> i64 {
+        if v == t {
+            return up;
+        }
+        while iter[v] < self.g[v].len() {
+            let InnerEdge { to, rev, cap } = self.g[v][iter[v]];
+            if cap > 0 && level[v] < level[to] {
+                let d = self.dfs(to, t, up.min(cap), level, iter);
+                if d > 0 {
+                    self.g[v][iter[v]].cap -= d;
+                    self.g[to][rev].cap += d;
+                    return d;
+                }
+            }
+            iter[v] += 1;
+        }
+        0
+    }
+    pub fn push_relabel(&mut self, s: usize, t: usize) -> i64 {
+        //! 最高ラベル優先のpush-relabel法で`s`から`t`へ流せるだけ流し、流した量を返す
+        //!
+        //! 容量の和が`i64`に収まらなくてもよいように、超過量は`i128`で持つ
+        assert_ne!(s, t);
+        let n = self.len();
+        let mut height = vec![0; n];
+        let mut excess = vec![0i128; n];
+        let mut iter = vec![0; n];
+        // 高さごとの活性な頂点
+        let mut buckets = vec![vec![]; 2 * n];
+        let mut highest = 0;
+        height[s] = n;
+        for i in 0..self.g[s].len() {
+            let InnerEdge { to, rev, cap } = self.g[s][i];
+            if cap == 0 {
+                continue;
+            }
+            self.g[s][i].cap = 0;
+            self.g[to][rev].cap += cap;
+            if excess[to] == 0 && to != s && to != t {
+                buckets[0].push(to);
+            }
+            excess[to] += cap as i128;
+        }
+        loop {
+            while highest > 0 && buckets[highest].is_empty() {
+                highest -= 1;
+            }
+            let Some(v) = buckets[highest].pop() else {
+                break;
+            };
+            while excess[v] > 0 {
+                if iter[v] == self.g[v].len() {
+                    // 残余辺の先で最も低い頂点より1高くする
+                    height[v] = self.g[v]
+                        .iter()
+                        .filter(|e| e.cap > 0)
+                        .map(|e| height[e.to] + 1)
+                        .min()
+                        .unwrap();
+                    iter[v] = 0;
+                    continue;
+                }
+                let InnerEdge { to, rev, cap } = self.g[v][iter[v]];
+                if cap > 0 && height[v] == height[to] + 1 {
+                    let d = excess[v].min(cap as i128) as i64;
+                    self.g[v][iter[v]].cap -= d;
+                    self.g[to][rev].cap += d;
+                    excess[v] -= d as i128;
+                    if excess[to] == 0 && to != s && to != t {
+                        buckets[height[to]].push(to);
+                        highest = highest.max(height[to]);
+                    }
+                    excess[to] += d as i128;
+                } else {
+                    iter[v] += 1;
+                }
+            }
+        }
+        excess[t] as i64
+    }
+    pub fn min_cut(&self, s: usize) -> Vec<bool> {
+        //! 流した後の残余グラフで`s`から到達できる頂点
+        //!
+        //! これが最小カットの`s`側になる
+        let mut seen = vec![false; self.len()];
+        let mut q = std::collections::VecDeque::new();
+        seen[s] = true;
+        q.push_back(s);
+        while let Some(v) = q.pop_front() {
+            for e in &self.g[v] {
+                if e.cap > 0 && !seen[e.to] {
+                    seen[e.to] = true;
+                    q.push_back(e.to);
+                }
+            }
+        }
+        seen
+    }
+}
+
+/// 流量の下限つきの流れ
+///
+/// 超頂点を足した`MaxFlow`に帰着する
+///
+/// `circulation`と`max_flow`は、辺を追加し終えてからどちらか一度だけ呼ぶ
+#[snippet("LowerBoundFlow")]
+#[snippet(include = "MaxFlow")]
+#[derive(Debug, Clone)]
+pub struct LowerBoundFlow {
+    n: usize,
+    mf: MaxFlow,
+    /// 頂点に流れ込む下限の和から、流れ出る下限の和を引いたもの
+    demand: Vec<i64>,
+    /// 各辺の下限
+    lower: Vec<i64>,
+}
+#[snippet("LowerBoundFlow")]
+impl LowerBoundFlow {
+    pub fn new(n: usize) -> Self {
+        LowerBoundFlow {
+            n,
+            mf: MaxFlow::new(n + 2),
+            demand: vec![0; n],
+            lower: vec![],
+        }
+    }
+    pub fn add_edge(&mut self, from: usize, to: usize, lower: i64, upper: i64) -> usize {
+        //! 流量が`lower`以上`upper`以下の辺を追加し、その番号を返す
+        assert!(0 <= lower && lower <= upper);
+        self.demand[to] += lower;
+        self.demand[from] -= lower;
+        self.lower.push(lower);
+        self.mf.add_edge(from, to, upper - lower)
+    }
+    fn feasible(&mut self) -> bool {
+        let (ss, tt) = (self.n, self.n + 1);
+        let mut need = 0;
+        for v in 0..self.n {
+            if self.demand[v] > 0 {
+                self.mf.add_edge(ss, v, self.demand[v]);
+                need += self.demand[v];
+            } else if self.demand[v] < 0 {
+                self.mf.add_edge(v, tt, -self.demand[v]);
+            }
+        }
+        self.mf.flow(ss, tt) == need
+    }
+    pub fn circulation(&mut self) -> bool {
+        //! 全ての辺の下限を満たす循環流があるか
+        //!
+        //! あれば、`get_flow`で各辺の流量がわかる
+        self.feasible()
+    }
+    pub fn max_flow(&mut self, s: usize, t: usize) -> Option<i64> {
+        //! 下限を満たしつつ`s`から`t`へ流せる最大量
+        //!
+        //! 下限を満たせなければ`None`
+        assert_ne!(s, t);
+        let back = self.mf.add_edge(t, s, i64::MAX);
+        if !self.feasible() {
+            return None;
+        }
+        let f = self.mf.get_edge(back).flow;
+        self.mf.change_edge(back, 0, 0);
+        Some(f + self.mf.flow(s, t))
+    }
+    pub fn get_flow(&self, id: usize) -> i64 {
+        //! 辺`id`の流量
+        self.lower[id] + self.mf.get_edge(id).flow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn max_flow() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..100 {
+            let n = rng.gen_range(2..10);
+            let m = rng.gen_range(0..25);
+            let mut mf = MaxFlow::new(n);
+            for _ in 0..m {
+                let (u, v) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                mf.add_edge(u, v, rng.gen_range(0..10));
+            }
+            let mut pr = mf.clone();
+            let f = mf.flow(0, n - 1);
+            assert_eq!(pr.push_relabel(0, n - 1), f);
+            for g in [&mf, &pr] {
+                // 流量保存と容量制約
+                let mut balance = vec![0; n];
+                for e in g.edges() {
+                    assert!(0 <= e.flow && e.flow <= e.cap);
+                    balance[e.from] -= e.flow;
+                    balance[e.to] += e.flow;
+                }
+                assert_eq!(balance[0], -f);
+                assert_eq!(balance[n - 1], f);
+                assert!(balance[1..n - 1].iter().all(|&b| b == 0));
+                // 最小カットの容量が最大流に等しい
+                let cut = g.min_cut(0);
+                assert!(cut[0] && !cut[n - 1]);
+                let cap: i64 = g
+                    .edges()
+                    .iter()
+                    .filter(|e| cut[e.from] && !cut[e.to])
+                    .map(|e| e.cap)
+                    .sum();
+                assert_eq!(cap, f);
+            }
+        }
+    }
+
+    #[test]
+    fn push_relabel_large_capacity() {
+        // sから出る容量の和がi64に収まらない
+        let mut mf = MaxFlow::new(4);
+        mf.add_edge(0, 1, i64::MAX);
+        mf.add_edge(0, 2, i64::MAX);
+        mf.add_edge(1, 3, 5);
+        mf.add_edge(2, 3, 7);
+        mf.add_edge(1, 2, i64::MAX);
+        let mut pr = mf.clone();
+        assert_eq!(mf.flow(0, 3), 12);
+        assert_eq!(pr.push_relabel(0, 3), 12);
+    }
+
+    #[test]
+    fn lower_bound() {
+        // 0 -> 1 -> 2 -> 0 の循環で、1 -> 2 に下限がある
+        let mut lb = LowerBoundFlow::new(3);
+        let a = lb.add_edge(0, 1, 0, 5);
+        let b = lb.add_edge(1, 2, 3, 4);
+        let c = lb.add_edge(2, 0, 0, 10);
+        assert!(lb.circulation());
+        let (fa, fb, fc) = (lb.get_flow(a), lb.get_flow(b), lb.get_flow(c));
+        assert!(fa == fb && fb == fc && (3..=4).contains(&fb));
+
+        // 下限を満たせない
+        let mut lb = LowerBoundFlow::new(3);
+        lb.add_edge(0, 1, 0, 2);
+        lb.add_edge(1, 2, 3, 4);
+        lb.add_edge(2, 0, 0, 10);
+        assert!(!lb.circulation());
+
+        // s = 0, t = 3 の最大流
+        let mut lb = LowerBoundFlow::new(4);
+        let e01 = lb.add_edge(0, 1, 0, 4);
+        let e02 = lb.add_edge(0, 2, 2, 3);
+        let e13 = lb.add_edge(1, 3, 1, 2);
+        let e23 = lb.add_edge(2, 3, 0, 5);
+        let e12 = lb.add_edge(1, 2, 0, 1);
+        assert_eq!(lb.max_flow(0, 3), Some(6));
+        assert_eq!(lb.get_flow(e01), lb.get_flow(e13) + lb.get_flow(e12));
+        assert_eq!(lb.get_flow(e02) + lb.get_flow(e12), lb.get_flow(e23));
+        assert!((2..=3).contains(&lb.get_flow(e02)));
+
+        let mut lb = LowerBoundFlow::new(3);
+        lb.add_edge(0, 1, 0, 1);
+        lb.add_edge(1, 2, 2, 2);
+        assert_eq!(lb.max_flow(0, 2), None);
+    }
+}
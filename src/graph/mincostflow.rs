@@ -0,0 +1,280 @@
+use cargo_snippet::snippet;
+
+use crate::{
+    algebra::Additive,
+    graph::{dijkstra_by, spfa},
+};
+
+/// 辺の状態
+#[snippet("MinCostFlow")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostFlowEdge {
+    pub from: usize,
+    pub to: usize,
+    pub cap: i64,
+    pub flow: i64,
+    pub cost: i64,
+}
+
+#[snippet("MinCostFlow")]
+#[derive(Debug, Clone)]
+struct InnerEdge {
+    to: usize,
+    /// `g[to]`での逆辺の位置
+    rev: usize,
+    /// 残余容量
+    cap: i64,
+    cost: i64,
+}
+
+/// 最小費用流
+///
+/// ポテンシャルつきのDijkstra法で最短路を求める主双対法
+///
+/// 流量を`F`として`O(F |E| log|V|)`
+#[snippet("MinCostFlow")]
+#[snippet(include = "Additive")]
+#[snippet(include = "dijkstra_by")]
+#[snippet(include = "spfa")]
+#[derive(Debug, Clone)]
+pub struct MinCostFlow {
+    g: Vec<Vec<InnerEdge>>,
+    /// `add_edge`で追加した辺の、`(from, g[from]での位置)`
+    pos: Vec<(usize, usize)>,
+}
+#[snippet("MinCostFlow")]
+impl MinCostFlow {
+    pub fn new(n: usize) -> Self {
+        MinCostFlow {
+            g: vec![vec![]; n],
+            pos: vec![],
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.g.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.g.is_empty()
+    }
+    pub fn add_edge(&mut self, from: usize, to: usize, cap: i64, cost: i64) -> usize {
+        //! 容量`cap`、単位流量あたりの費用`cost`の辺を追加し、その番号を返す
+        //!
+        //! `cost`は負でもよいが、負閉路はないこと
+        assert!(0 <= cap);
+        let id = self.pos.len();
+        let from_idx = self.g[from].len();
+        // 自己ループでは逆辺が同じ頂点の次の位置に入る
+        let to_idx = self.g[to].len() + (from == to) as usize;
+        self.pos.push((from, from_idx));
+        self.g[from].push(InnerEdge {
+            to,
+            rev: to_idx,
+            cap,
+            cost,
+        });
+        self.g[to].push(InnerEdge {
+            to: from,
+            rev: from_idx,
+            cap: 0,
+            cost: -cost,
+        });
+        id
+    }
+    pub fn get_edge(&self, id: usize) -> CostFlowEdge {
+        let (from, idx) = self.pos[id];
+        let e = &self.g[from][idx];
+        let re = &self.g[e.to][e.rev];
+        CostFlowEdge {
+            from,
+            to: e.to,
+            cap: e.cap + re.cap,
+            flow: re.cap,
+            cost: e.cost,
+        }
+    }
+    pub fn edges(&self) -> Vec<CostFlowEdge> {
+        (0..self.pos.len()).map(|id| self.get_edge(id)).collect()
+    }
+    pub fn flow(&mut self, s: usize, t: usize) -> (i64, i64) {
+        //! `s`から`t`へ流せるだけ流し、`(流量, 費用)`を返す
+        self.flow_with_limit(s, t, i64::MAX)
+    }
+    pub fn flow_with_limit(&mut self, s: usize, t: usize, limit: i64) -> (i64, i64) {
+        //! `s`から`t`へ`limit`まで流し、`(流量, 費用)`を返す
+        *self.slope(s, t, limit).last().unwrap()
+    }
+    pub fn slope(&mut self, s: usize, t: usize, limit: i64) -> Vec<(i64, i64)> {
+        //! 流量に対する最小費用の折れ線を、折れ目の`(流量, 費用)`の列で返す
+        //!
+        //! 先頭は`(0, 0)`で、末尾が`limit`まで流したときの値
+        assert_ne!(s, t);
+        let n = self.len();
+        let mut h = self.initial_potential(s);
+        let mut ret = vec![(0, 0)];
+        let (mut flow, mut cost) = (0, 0);
+        while flow < limit {
+            // 被約費用は非負になる
+            let residual: Vec<Vec<(i64, usize)>> = (0..n)
+                .map(|v| {
+                    self.g[v]
+                        .iter()
+                        .filter(|e| e.cap > 0)
+                        .map(|e| (e.cost + h[v] - h[e.to], e.to))
+                        .collect()
+                })
+                .collect();
+            let sp = dijkstra_by::<Additive<i64>, _>(&[s], &residual, Some(t));
+            let Some(dt) = sp.dist[t] else {
+                break;
+            };
+            // 最短路木の親から、使う辺を選ぶ
+            let mut path = vec![];
+            let mut v = t;
+            while let Some(p) = sp.prev[v] {
+                let dd = sp.dist[v].unwrap() - sp.dist[p].unwrap();
+                let i = (0..self.g[p].len())
+                    .find(|&i| {
+                        let e = &self.g[p][i];
+                        e.to == v && e.cap > 0 && e.cost + h[p] - h[v] == dd
+                    })
+                    .unwrap();
+                path.push((p, i));
+                v = p;
+            }
+            // 確定していない頂点は、tと同じだけずらす
+            for (hv, d) in h.iter_mut().zip(&sp.dist) {
+                *hv += d.map_or(dt, |d| d.min(dt));
+            }
+            let d = path
+                .iter()
+                .map(|&(v, i)| self.g[v][i].cap)
+                .fold(limit - flow, i64::min);
+            for &(v, i) in &path {
+                let InnerEdge { to, rev, .. } = self.g[v][i];
+                self.g[v][i].cap -= d;
+                self.g[to][rev].cap += d;
+            }
+            let unit = h[t] - h[s];
+            flow += d;
+            cost += d * unit;
+            // 傾きが同じなら折れ目をまとめる
+            if ret.len() >= 2 {
+                let (f0, c0) = ret[ret.len() - 2];
+                let (f1, c1) = ret[ret.len() - 1];
+                if (c1 - c0) * (flow - f1) == (cost - c1) * (f1 - f0) {
+                    ret.pop();
+                }
+            }
+            ret.push((flow, cost));
+        }
+        ret
+    }
+    fn initial_potential(&self, s: usize) -> Vec<i64> {
+        //! 負の費用の辺があれば、残余グラフでの`s`からの距離をポテンシャルにする
+        let n = self.len();
+        if self.g.iter().flatten().all(|e| e.cap == 0 || e.cost >= 0) {
+            return vec![0; n];
+        }
+        let residual: Vec<Vec<(isize, usize)>> = (0..n)
+            .map(|v| {
+                self.g[v]
+                    .iter()
+                    .filter(|e| e.cap > 0)
+                    .map(|e| (e.cost as isize, e.to))
+                    .collect()
+            })
+            .collect();
+        let sp = spfa(s, &residual).expect("negative cycle");
+        sp.dist.iter().map(|d| d.unwrap_or(0) as i64).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn min_cost_flow() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 1 -> 2
+        let mut mcf = MinCostFlow::new(4);
+        let e01 = mcf.add_edge(0, 1, 2, 1);
+        let e02 = mcf.add_edge(0, 2, 1, 2);
+        let e12 = mcf.add_edge(1, 2, 1, 1);
+        let e13 = mcf.add_edge(1, 3, 1, 3);
+        let e23 = mcf.add_edge(2, 3, 2, 1);
+        let mut slope_mcf = mcf.clone();
+        assert_eq!(mcf.flow(0, 3), (3, 10));
+        assert_eq!(
+            [e01, e02, e12, e13, e23].map(|e| mcf.get_edge(e).flow),
+            [2, 1, 1, 1, 2]
+        );
+        // 費用3, 3, 4 の道を順に使う
+        assert_eq!(slope_mcf.slope(0, 3, i64::MAX), [(0, 0), (2, 6), (3, 10)]);
+
+        let mut mcf = MinCostFlow::new(4);
+        for e in [
+            (0, 1, 2, 1),
+            (0, 2, 1, 2),
+            (1, 2, 1, 1),
+            (1, 3, 1, 3),
+            (2, 3, 2, 1),
+        ] {
+            mcf.add_edge(e.0, e.1, e.2, e.3);
+        }
+        // 自己ループは使われない
+        let lp = mcf.add_edge(1, 1, 5, 0);
+        assert_eq!(mcf.flow_with_limit(0, 3, 1), (1, 3));
+        assert_eq!(
+            mcf.get_edge(lp),
+            CostFlowEdge {
+                from: 1,
+                to: 1,
+                cap: 5,
+                flow: 0,
+                cost: 0
+            }
+        );
+    }
+
+    #[test]
+    fn negative_cost() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(0);
+        for _ in 0..100 {
+            // DAGなので負閉路はない
+            let n = rng.gen_range(2..8);
+            let mut edges = vec![];
+            for _ in 0..rng.gen_range(0..12) {
+                let (u, v) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                if u < v {
+                    edges.push((u, v, rng.gen_range(0..4), rng.gen_range(-4..6)));
+                }
+            }
+            let mut mcf = MinCostFlow::new(n);
+            for &(u, v, cap, cost) in &edges {
+                mcf.add_edge(u, v, cap, cost);
+            }
+            // 費用をずらしても、流量ごとの最小費用は変わらない
+            // 費用は頂点の番号の差に比例してずらす
+            let mut shifted = MinCostFlow::new(n);
+            for &(u, v, cap, cost) in &edges {
+                shifted.add_edge(u, v, cap, cost + 10 * (v - u) as i64);
+            }
+            let slope = mcf.slope(0, n - 1, i64::MAX);
+            let shifted_slope = shifted.slope(0, n - 1, i64::MAX);
+            assert_eq!(slope.len(), shifted_slope.len());
+            let shift = 10 * (n - 1) as i64;
+            for (&(f, c), &(sf, sc)) in slope.iter().zip(&shifted_slope) {
+                assert_eq!((f, c + shift * f), (sf, sc));
+            }
+            // 傾きは単調に増える
+            for w in slope.windows(3) {
+                assert!(
+                    (w[1].1 - w[0].1) * (w[2].0 - w[1].0) < (w[2].1 - w[1].1) * (w[1].0 - w[0].0)
+                );
+            }
+            let cost: i64 = mcf.edges().iter().map(|e| e.flow * e.cost).sum();
+            assert_eq!(cost, slope.last().unwrap().1);
+        }
+    }
+}
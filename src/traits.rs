@@ -3,8 +3,11 @@ use cargo_snippet::snippet;
 /// 位置`p`から`k`ターン先の行き先を求める
 pub trait Doubling {
     type D;
-    fn new(v: &[Self::D], k: usize) -> Self;
-    fn query(&self, k: usize, idx: usize) -> Self::D;
+    /// `max_step`ターンまで求められるように構築する
+    fn new(v: &[Self::D], max_step: u64) -> Self;
+    /// 求められるターン数の上限
+    fn max_step(&self) -> u64;
+    fn query(&self, k: u64, idx: usize) -> Self::D;
     fn set_next(&mut self, x: Self::D, idx: usize);
 }
 
@@ -37,6 +40,8 @@ pub trait Inv: Monoid {
     fn inv(s: &Self::S) -> Self::S;
 }
 pub trait Commutative {}
+/// 冪等な二項演算 (`op(a, a) == a`) を表すマーカー
+pub trait Idempotent {}
 
 pub trait CommutaitveGroup: Monoid + Commutative + Inv {}
 pub trait Group: Monoid + Inv {}